@@ -1,26 +1,50 @@
-use serde::Deserialize;
+use serde::{de, Deserialize, Deserializer};
 
-#[derive(Deserialize, Debug, Clone)]
-#[serde(tag = "type", content = "value")]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Color {
     Rgba(Rgba),
     Gradient(LinearGradient),
 }
 
+impl<'de> Deserialize<'de> for Color {
+    /// a bare `[r, g, b, a]` is accepted as `Color::Rgba`
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(tag = "type", content = "value")]
+        enum Tagged {
+            Rgba(Rgba),
+            Gradient(LinearGradient),
+        }
+        // only an array is a bare rgba, anything else reports the error of
+        // the tagged form
+        let value = serde_json::Value::deserialize(deserializer)?;
+        if value.is_array() {
+            let rgba = Rgba::deserialize(value).map_err(de::Error::custom)?;
+            return Ok(Color::Rgba(rgba));
+        }
+        Ok(
+            match Tagged::deserialize(value).map_err(de::Error::custom)? {
+                Tagged::Rgba(rgba) => Color::Rgba(rgba),
+                Tagged::Gradient(gradient) => Color::Gradient(gradient),
+            },
+        )
+    }
+}
+
 impl Default for Color {
     fn default() -> Self {
         Self::Rgba(Rgba(0, 0, 0, 0))
     }
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Debug, Clone, PartialEq)]
 #[serde(tag = "type", content = "value")]
 pub enum GradientStopPosition {
     Percent(f32),
     Pixel(f32),
 }
 
-#[derive(Deserialize, Debug, Clone, Copy)]
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
 pub struct Rgba(pub u8, pub u8, pub u8, pub u8);
 
 impl From<Rgba> for tiny_skia::Color {
@@ -29,13 +53,13 @@ impl From<Rgba> for tiny_skia::Color {
     }
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Debug, Clone, PartialEq)]
 pub struct LinearGradient {
     pub angle: f32,
     pub stops: Vec<ColorStop>,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Debug, Clone, PartialEq)]
 pub struct ColorStop {
     pub position: GradientStopPosition,
     pub color: Rgba,
@@ -1,37 +1,202 @@
+use image::{imageops::FilterType, RgbaImage};
 use rusttype::Font;
-use std::{borrow::Borrow, cell::RefCell, collections::HashMap, rc::Rc, sync::Arc};
+use rustybuzz::{Face, Tag};
+use serde::{Deserialize, Deserializer};
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use ttf_parser::{GlyphId, RasterImageFormat};
 
-use crate::utils::{make_error, AppResult};
+use crate::{
+    color::Rgba,
+    utils::{make_error, AppResult},
+};
 
-use once_cell::sync::Lazy;
+/// a font of the `font_set`, parsed once for rasterizing and once for shaping
+pub struct Typeface {
+    pub font: Font<'static>,
+    pub face: Face<'static>,
+    bitmaps: RefCell<BitmapCache>,
+}
 
-pub static mut FONTS: Lazy<HashMap<String, Font<'static>>> = Lazy::new(|| HashMap::new());
-pub static mut DEFAULT_FONT: Lazy<Option<&Font<'static>>> = Lazy::new(|| {
-    #[cfg(feature = "default-font")]
-    return Some(Box::leak(Box::new(init_default_font())));
-    None
-});
+/// decoded color bitmaps by glyph and the bits of the pixels per em
+type BitmapCache = HashMap<(u16, u32), Option<Rc<GlyphBitmap>>>;
+
+impl Typeface {
+    fn parse(data: &'static [u8]) -> Option<Self> {
+        Some(Self {
+            font: Font::try_from_bytes(data)?,
+            face: Face::from_slice(data, 0)?,
+            bitmaps: RefCell::new(HashMap::new()),
+        })
+    }
+    /// whether the font has a glyph for the character
+    pub fn covers(&self, c: char) -> bool {
+        self.face.glyph_index(c).is_some()
+    }
+    /// color bitmap of the glyph from the CBDT or sbix table, scaled from the
+    /// nearest strike. it is decoded once for every size, the text is shaped
+    /// again on every measure
+    pub fn bitmap(&self, glyph: u16, pixels_per_em: f32) -> Option<Rc<GlyphBitmap>> {
+        let key = (glyph, pixels_per_em.to_bits());
+        if let Some(bitmap) = self.bitmaps.borrow().get(&key) {
+            return bitmap.clone();
+        }
+        let bitmap = self.decode_bitmap(glyph, pixels_per_em).map(Rc::new);
+        self.bitmaps.borrow_mut().insert(key, bitmap.clone());
+        bitmap
+    }
+    fn decode_bitmap(&self, glyph: u16, pixels_per_em: f32) -> Option<GlyphBitmap> {
+        let raster = self
+            .face
+            .glyph_raster_image(GlyphId(glyph), pixels_per_em.round() as u16)?;
+        if !matches!(raster.format, RasterImageFormat::PNG) {
+            return None;
+        }
+        let image = image::load_from_memory(raster.data).ok()?.to_rgba8();
+        let scale = pixels_per_em / raster.pixels_per_em as f32;
+        let w = (image.width() as f32 * scale).round().max(1.) as u32;
+        let h = (image.height() as f32 * scale).round().max(1.) as u32;
+        Some(GlyphBitmap {
+            left: raster.x as f32 * scale,
+            top: -(raster.y as f32 + raster.height as f32) * scale,
+            image: image::imageops::resize(&image, w, h, FilterType::Triangle),
+        })
+    }
+    /// layers of the glyph from the COLR table (version 0) with their colors
+    /// in the first palette, bottom up. `None` is the color of the text
+    pub fn color_layers(&self, glyph: u16) -> Option<Vec<(u16, Option<Rgba>)>> {
+        let colr = self.face.table_data(Tag::from_bytes(b"COLR"))?;
+        let cpal = self.face.table_data(Tag::from_bytes(b"CPAL"))?;
+        colr_layers(colr, cpal, glyph)
+    }
+}
+
+/// layers of the glyph in the raw COLR (version 0) and CPAL tables, `None`
+/// when the glyph has no layers or the tables are cut short
+pub(crate) fn colr_layers(
+    colr: &[u8],
+    cpal: &[u8],
+    glyph: u16,
+) -> Option<Vec<(u16, Option<Rgba>)>> {
+    let bases = read_u16(colr, 2)? as usize;
+    let bases_offset = read_u32(colr, 4)? as usize;
+    let layers_offset = read_u32(colr, 8)? as usize;
+    // base glyph records are sorted by glyph id
+    let (mut lo, mut hi) = (0, bases);
+    let base = loop {
+        if lo >= hi {
+            return None;
+        }
+        let mid = (lo + hi) / 2;
+        let record = bases_offset + mid * 6;
+        match read_u16(colr, record)?.cmp(&glyph) {
+            std::cmp::Ordering::Less => lo = mid + 1,
+            std::cmp::Ordering::Greater => hi = mid,
+            std::cmp::Ordering::Equal => break record,
+        }
+    };
+    let first = read_u16(colr, base + 2)? as usize;
+    let count = read_u16(colr, base + 4)? as usize;
+    let colors_offset = read_u32(cpal, 8)? as usize;
+    let palette = read_u16(cpal, 12)? as usize;
+    (first..first + count)
+        .map(|layer| {
+            let record = layers_offset + layer * 4;
+            let index = read_u16(colr, record + 2)?;
+            let color = match index {
+                0xFFFF => None,
+                index => {
+                    let color = colors_offset + (palette + index as usize) * 4;
+                    let bgra = cpal.get(color..color + 4)?;
+                    Some(Rgba(bgra[2], bgra[1], bgra[0], bgra[3]))
+                }
+            };
+            Some((read_u16(colr, record)?, color))
+        })
+        .collect()
+}
+
+/// a color bitmap glyph, `left` and `top` are relative to the pen position
+/// on the baseline
+pub struct GlyphBitmap {
+    pub left: f32,
+    pub top: f32,
+    pub image: RgbaImage,
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    data.get(offset..offset + 2)
+        .map(|b| u16::from_be_bytes([b[0], b[1]]))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+/// families of the `font_set` in the order of fallback, a single name is
+/// accepted as well
+#[derive(Debug, Clone, Default)]
+pub struct FontFamilies(pub Vec<String>);
+
+impl<'de> Deserialize<'de> for FontFamilies {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            One(String),
+            List(Vec<String>),
+        }
+        Ok(match Raw::deserialize(deserializer)? {
+            Raw::One(family) => FontFamilies(vec![family]),
+            Raw::List(families) => FontFamilies(families),
+        })
+    }
+}
+
+thread_local! {
+    static TYPEFACES: RefCell<HashMap<String, &'static Typeface>> = RefCell::new(HashMap::new());
+    static DEFAULT_TYPEFACE: Option<&'static Typeface> = init_default_typeface();
+}
 
 #[cfg(feature = "default-font")]
-fn init_default_font() -> Font<'static> {
-    let FONT_DATA: &[u8] = include_bytes!("../../assets/default.ttf");
-    Font::try_from_bytes(FONT_DATA).unwrap()
+fn init_default_typeface() -> Option<&'static Typeface> {
+    let data: &'static [u8] = include_bytes!("../../assets/default.ttf");
+    Typeface::parse(data).map(|typeface| &*Box::leak(Box::new(typeface)))
+}
+
+#[cfg(not(feature = "default-font"))]
+fn init_default_typeface() -> Option<&'static Typeface> {
+    None
 }
 
 pub fn init_fonts(fontset: HashMap<String, Vec<u8>>) -> AppResult {
     for (font_family, buff) in fontset {
-        unsafe {
-            FONTS.insert(
-                font_family,
-                Font::try_from_bytes(Box::leak(Box::new(buff))).unwrap(),
-            );
-        }
+        // fonts live as long as the module, glyphs borrow from them
+        let data: &'static [u8] = Box::leak(buff.into_boxed_slice());
+        let typeface = Typeface::parse(data).ok_or(make_error("font is invalid!"))?;
+        let typeface: &'static Typeface = Box::leak(Box::new(typeface));
+        TYPEFACES.with(|typefaces| typefaces.borrow_mut().insert(font_family, typeface));
     }
-    #[cfg(feature = "default-font")]
-    init_default_font();
     Ok(())
 }
 
-pub fn get_font(font: String) -> Option<&'static Font<'static>> {
-    unsafe { FONTS.get(&font).or(**DEFAULT_FONT.borrow()) }
+/// the registered fonts of the families followed by the bundled default
+/// font, the unnamed font stands in for an empty list
+pub fn get_typefaces(families: &[String]) -> Vec<&'static Typeface> {
+    let unnamed = [String::new()];
+    let families = match families.is_empty() {
+        true => &unnamed[..],
+        false => families,
+    };
+    let mut typefaces: Vec<&'static Typeface> = TYPEFACES.with(|typefaces| {
+        let typefaces = typefaces.borrow();
+        families
+            .iter()
+            .filter_map(|family| typefaces.get(family).copied())
+            .collect()
+    });
+    if let Some(default) = DEFAULT_TYPEFACE.with(|typeface| *typeface) {
+        typefaces.push(default);
+    }
+    typefaces
 }
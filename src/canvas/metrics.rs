@@ -1,10 +1,157 @@
-use serde::Deserialize;
+use std::{fmt, marker::PhantomData};
+
+use serde::{de, Deserialize, Deserializer, Serialize};
 use tiny_skia::{Point, Rect};
 
-#[derive(Deserialize, Debug, Clone, Copy)]
-pub struct Position {
-    pub x: Option<f32>,
-    pub y: Option<f32>,
+/// size `Length::Vw` and `Length::Vh` refer to, the canvas passes it down
+/// through the layout of every graphic
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Viewport {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl From<Size> for Viewport {
+    fn from(size: Size) -> Self {
+        Viewport {
+            width: size.width(),
+            height: size.height(),
+        }
+    }
+}
+
+/// a number is in pixels, strings may use a unit: `"12px"`, `"50%"` of the
+/// layout bounds, or `"10vw"` / `"10vh"` of the root size
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Length {
+    Px(f32),
+    Percent(f32),
+    Vw(f32),
+    Vh(f32),
+}
+
+impl Length {
+    /// pixels, `base` is the length a percentage refers to
+    pub fn resolve(&self, base: f32, viewport: Viewport) -> f32 {
+        match *self {
+            Length::Px(px) => px,
+            Length::Percent(percent) => base * percent / 100.,
+            Length::Vw(vw) => viewport.width * vw / 100.,
+            Length::Vh(vh) => viewport.height * vh / 100.,
+        }
+    }
+}
+
+impl From<f32> for Length {
+    fn from(value: f32) -> Self {
+        Length::Px(value)
+    }
+}
+
+impl<'de> Deserialize<'de> for Length {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Number(f32),
+            String(String),
+        }
+        let value = match Raw::deserialize(deserializer)? {
+            Raw::Number(px) => return Ok(Length::Px(px)),
+            Raw::String(value) => value,
+        };
+        let value = value.trim();
+        let (unit, number): (fn(f32) -> Length, _) = if let Some(n) = value.strip_suffix('%') {
+            (Length::Percent, n)
+        } else if let Some(n) = value.strip_suffix("vw") {
+            (Length::Vw, n)
+        } else if let Some(n) = value.strip_suffix("vh") {
+            (Length::Vh, n)
+        } else {
+            (Length::Px, value.strip_suffix("px").unwrap_or(value))
+        };
+        number
+            .trim()
+            .parse()
+            .map(unit)
+            .map_err(|_| de::Error::custom(format!("invalid length `{value}`")))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Position<T = f32> {
+    pub x: Option<T>,
+    pub y: Option<T>,
+    /// distance from the right edge of the layout bounds, ignored when `x` is set
+    pub right: Option<T>,
+    /// distance from the bottom edge of the layout bounds, ignored when `y` is set
+    pub bottom: Option<T>,
+}
+
+/// a map of the fields, or the `[x, y]` pair accepted before the anchors
+/// were added
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Position<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Fields<T> {
+            x: Option<T>,
+            y: Option<T>,
+            right: Option<T>,
+            bottom: Option<T>,
+        }
+        struct PositionVisitor<T>(PhantomData<T>);
+        impl<'de, T: Deserialize<'de>> de::Visitor<'de> for PositionVisitor<T> {
+            type Value = Position<T>;
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a position map or an [x, y] array")
+            }
+            fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let x = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                let y = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                if seq.next_element::<de::IgnoredAny>()?.is_some() {
+                    return Err(de::Error::invalid_length(3, &self));
+                }
+                Ok(Position {
+                    x,
+                    y,
+                    right: None,
+                    bottom: None,
+                })
+            }
+            fn visit_map<A: de::MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+                let Fields {
+                    x,
+                    y,
+                    right,
+                    bottom,
+                } = Fields::deserialize(de::value::MapAccessDeserializer::new(map))?;
+                Ok(Position {
+                    x,
+                    y,
+                    right,
+                    bottom,
+                })
+            }
+        }
+        deserializer.deserialize_any(PositionVisitor(PhantomData))
+    }
+}
+
+impl Position<Length> {
+    /// percentages of x and right refer to the width of `bounds`, the others to its height
+    pub fn resolve(&self, bounds: &Rect, viewport: Viewport) -> Position {
+        let (w, h) = (bounds.width(), bounds.height());
+        Position {
+            x: self.x.map(|x| x.resolve(w, viewport)),
+            y: self.y.map(|y| y.resolve(h, viewport)),
+            right: self.right.map(|right| right.resolve(w, viewport)),
+            bottom: self.bottom.map(|bottom| bottom.resolve(h, viewport)),
+        }
+    }
 }
 
 impl Position {
@@ -26,11 +173,13 @@ impl Position {
     }
 }
 
-impl From<(f32, f32)> for Position {
+impl<T: From<f32>> From<(f32, f32)> for Position<T> {
     fn from((x, y): (f32, f32)) -> Self {
         Self {
-            x: Some(x),
-            y: Some(y),
+            x: Some(x.into()),
+            y: Some(y.into()),
+            right: None,
+            bottom: None,
         }
     }
 }
@@ -40,6 +189,8 @@ impl From<Rect> for Position {
         Self {
             x: Some(value.left()),
             y: Some(value.top()),
+            right: None,
+            bottom: None,
         }
     }
 }
@@ -49,6 +200,8 @@ impl From<&Rect> for Position {
         Self {
             x: Some(value.left()),
             y: Some(value.top()),
+            right: None,
+            bottom: None,
         }
     }
 }
@@ -56,8 +209,8 @@ impl From<&Rect> for Position {
 impl From<Position> for Point {
     fn from(value: Position) -> Self {
         Point {
-            x: value.x.or(Some(0.)).unwrap(),
-            y: value.y.or(Some(0.)).unwrap(),
+            x: value.x(),
+            y: value.y(),
         }
     }
 }
@@ -67,14 +220,25 @@ impl Default for Position {
         Self {
             x: Some(0.),
             y: Some(0.),
+            right: None,
+            bottom: None,
         }
     }
 }
 
 #[derive(Deserialize, Debug, Clone, Copy)]
-pub struct Size {
-    pub width: Option<f32>,
-    pub height: Option<f32>,
+pub struct Size<T = f32> {
+    pub width: Option<T>,
+    pub height: Option<T>,
+}
+
+impl Size<Length> {
+    pub fn resolve(&self, bounds: &Rect, viewport: Viewport) -> Size {
+        Size {
+            width: self.width.map(|w| w.resolve(bounds.width(), viewport)),
+            height: self.height.map(|h| h.resolve(bounds.height(), viewport)),
+        }
+    }
 }
 
 impl Size {
@@ -92,11 +256,11 @@ impl Size {
     }
 }
 
-impl From<(f32, f32)> for Size {
+impl<T: From<f32>> From<(f32, f32)> for Size<T> {
     fn from((w, h): (f32, f32)) -> Self {
         Self {
-            width: Some(w),
-            height: Some(h),
+            width: Some(w.into()),
+            height: Some(h.into()),
         }
     }
 }
@@ -128,7 +292,20 @@ impl Default for Size {
 }
 
 #[derive(Deserialize, Debug, Clone, Copy)]
-pub struct Padding(f32, f32, f32, f32);
+pub struct Padding<T = f32>(T, T, T, T);
+
+impl Padding<Length> {
+    /// percentages of top and bottom refer to the height of `bounds`, the others to its width
+    pub fn resolve(&self, bounds: &Rect, viewport: Viewport) -> Padding {
+        let (w, h) = (bounds.width(), bounds.height());
+        Padding(
+            self.0.resolve(h, viewport),
+            self.1.resolve(w, viewport),
+            self.2.resolve(h, viewport),
+            self.3.resolve(w, viewport),
+        )
+    }
+}
 
 impl Default for Padding {
     fn default() -> Self {
@@ -150,4 +327,32 @@ impl Padding {
     pub fn left(&self) -> f32 {
         self.3
     }
+    /// sum of the left and right sides, and of the top and bottom sides
+    pub fn size(&self) -> Size {
+        (self.left() + self.right(), self.top() + self.bottom()).into()
+    }
+    /// offset of the top left corner of the content
+    pub fn offset(&self) -> Size {
+        (self.left(), self.top()).into()
+    }
+}
+
+/// resolved rect of a graphic, as returned by the layout api
+#[derive(Serialize, Debug, Clone, Copy)]
+pub struct Bounds {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl From<Rect> for Bounds {
+    fn from(value: Rect) -> Self {
+        Self {
+            x: value.x(),
+            y: value.y(),
+            width: value.width(),
+            height: value.height(),
+        }
+    }
 }
@@ -6,11 +6,16 @@ pub mod metrics;
 use std::collections::HashMap;
 
 use crate::{
-    clip_pixmap, empty_pixmap, empty_rect,
-    graphic::{Draw, DrawResult},
+    clip_pixmap,
+    drawing::shader::create_shader,
+    empty_pixmap, empty_rect,
+    graphic::{container::Corner, Draw, DrawResult, Layout, Measure},
+    lt_rect, merge_pixmap, paint, xywh_rect,
 };
 use serde::Deserialize;
-use tiny_skia::{FillRule, Paint, PathBuilder, Pixmap, PixmapPaint, Rect, Transform};
+use tiny_skia::{
+    BlendMode, FillRule, FilterQuality, Paint, PathBuilder, Pixmap, PixmapPaint, Rect, Transform,
+};
 use wasm_bindgen::JsValue;
 
 use crate::{
@@ -25,6 +30,10 @@ use self::font::init_fonts;
 #[serde(rename_all = "camelCase")]
 pub struct CanvasConfiguration {
     pub font_set: Option<HashMap<String, Vec<u8>>>,
+    /// exact output size in pixels, content outside of it is clipped.
+    /// when omitted, the output fits the bounds of the root graphic
+    pub size: Option<Size>,
+    pub background: Option<color::Color>,
     pub graphic: graphic::container::Container,
 }
 
@@ -40,35 +49,123 @@ impl CanvasConfiguration {
         if let Some(font_set) = self.font_set {
             init_fonts(font_set)?;
         }
-        let canvas = Canvas::new().draw(Graphic::Container(self.graphic))?;
-        Ok(canvas)
+        let canvas = match self.size {
+            Some(size) => Canvas::with_size(size)?,
+            None => Canvas::new(),
+        };
+        canvas
+            .draw(Graphic::Container(Box::new(self.graphic)))?
+            .fill_background(self.background)
+    }
+
+    /// resolved rect of every graphic which has an id, nothing is painted
+    pub fn layout(self) -> AppResult<HashMap<String, Bounds>> {
+        if let Some(font_set) = self.font_set {
+            init_fonts(font_set)?;
+        }
+        let canvas = match self.size {
+            Some(size) => Canvas::with_size(size)?,
+            None => Canvas::new(),
+        };
+        let mut graphic = Graphic::Container(Box::new(self.graphic));
+        canvas.layout(&mut graphic)?;
+        let mut bounds = HashMap::new();
+        graphic.collect_bounds(&mut bounds);
+        Ok(bounds)
     }
 }
 
 pub struct Canvas {
     pub pixmap: tiny_skia::Pixmap,
+    /// `None` means the canvas grows with the root graphic
+    pub size: Option<Size>,
+}
+
+impl Default for Canvas {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Canvas {
     pub fn new() -> Self {
         Self {
             pixmap: empty_pixmap!(1, 1),
+            size: None,
         }
     }
 
+    pub fn with_size(size: Size) -> AppResult<Self> {
+        let pixmap = Pixmap::new(size.width() as u32, size.height() as u32)
+            .ok_or(make_error("invalid canvas size!"))?;
+        Ok(Self {
+            pixmap,
+            size: Some(size),
+        })
+    }
+
+    /// resolves the rects of `graphic` within the canvas
+    pub fn layout(&self, graphic: &mut Graphic) -> AppResult<Rect> {
+        let (pb, lb) = match self.size {
+            Some(size) => (
+                lt_rect!(0., 0.),
+                xywh_rect!(0., 0., size.width(), size.height()),
+            ),
+            None => (empty_rect!(), empty_rect!()),
+        };
+        // root relative lengths refer to the canvas, or to the root graphic
+        // when the canvas grows with it
+        let viewport: Viewport = match self.size {
+            Some(size) => size.into(),
+            None => graphic.measure(pb, lb, Viewport::default())?.into(),
+        };
+        graphic.layout(pb, lb, viewport)
+    }
+
     pub fn draw(self, mut graphic: Graphic) -> AppResult<Self> {
-        let pb = empty_rect!();
-        let lb = empty_rect!();
-        let DrawResult(pixmap, bounds) = graphic.draw(self.pixmap, pb, lb)?;
+        self.layout(&mut graphic)?;
+        let DrawResult(pixmap, bounds) = graphic.draw(self.pixmap)?;
+        let bounds = match self.size {
+            Some(size) => xywh_rect!(0., 0., size.width(), size.height()),
+            None => bounds,
+        };
         Ok(Self {
             pixmap: clip_pixmap!(pixmap, bounds),
+            size: self.size,
+        })
+    }
+
+    /// paints `background` below everything drawn so far
+    pub fn fill_background(self, background: Option<color::Color>) -> AppResult<Self> {
+        let background = match background {
+            Some(background) => background,
+            None => return Ok(self),
+        };
+        let w = self.pixmap.width() as f32;
+        let h = self.pixmap.height() as f32;
+        let mut paint = paint!();
+        paint.shader = create_shader(
+            &background,
+            (0., 0.).into(),
+            (w, h).into(),
+            Corner::default(),
+        )?;
+        let mut pixmap = empty_pixmap!(w, h);
+        pixmap.fill_rect(
+            xywh_rect!(0., 0., w, h),
+            &paint,
+            Transform::identity(),
+            None,
+        );
+        Ok(Self {
+            pixmap: merge_pixmap!(pixmap, self.pixmap),
+            size: self.size,
         })
     }
 
     pub fn export(&self) -> AppResult<Vec<u8>> {
-        Ok(self
-            .pixmap
+        self.pixmap
             .encode_png()
-            .map_err(|_| make_error("export fail!"))?)
+            .map_err(|_| make_error("export fail!"))
     }
 }
@@ -0,0 +1,56 @@
+use tiny_skia::{Path, PathBuilder};
+
+use crate::{
+    graphic::container::Corner,
+    utils::{make_error, AppResult},
+};
+
+/// `corner` is expected to be fitted to the rect already, see `Corner::get_fitted`
+pub fn rounded_rect(x: f32, y: f32, w: f32, h: f32, corner: Corner) -> AppResult<Path> {
+    let mut pb = PathBuilder::new();
+    let Corner(c0, c1, c2, c3) = corner;
+    let (mid_x, mid_y) = (x + w / 2., y + h / 2.);
+    let (max_x, max_y) = (x + w, y + h);
+    pb.move_to(x, mid_y);
+    if c0 == 0. {
+        pb.line_to(x, y);
+    } else {
+        let offset = 0.45 * c0;
+        pb.line_to(x, y + c0);
+        pb.cubic_to(x, y + offset, x + offset, y, x + c0, y);
+    }
+    pb.line_to(mid_x, y);
+    if c1 == 0. {
+        pb.line_to(max_x, y);
+    } else {
+        let offset = 0.45 * c1;
+        pb.line_to(max_x - c1, y);
+        pb.cubic_to(max_x - offset, y, max_x, y + offset, max_x, y + c1);
+    }
+    pb.line_to(max_x, mid_y);
+    if c2 == 0. {
+        pb.line_to(max_x, max_y);
+    } else {
+        let offset = 0.45 * c2;
+        pb.line_to(max_x, max_y - c2);
+        pb.cubic_to(
+            max_x,
+            max_y - offset,
+            max_x - offset,
+            max_y,
+            max_x - c2,
+            max_y,
+        );
+    }
+    pb.line_to(mid_x, max_y);
+    if c3 == 0. {
+        pb.line_to(x, max_y);
+    } else {
+        let offset = 0.45 * c3;
+        pb.line_to(x + c3, max_y);
+        pb.cubic_to(x + offset, max_y, x, max_y - offset, x, max_y - c3);
+    }
+    pb.line_to(x, mid_y);
+    pb.close();
+    pb.finish().ok_or(make_error("path generation fail!"))
+}
@@ -9,6 +9,20 @@ use crate::{
     utils::{make_error, AppResult},
 };
 
+pub fn create_shader<'a>(
+    color: &color::Color,
+    position: Position,
+    size: Size,
+    corner: Corner,
+) -> AppResult<Shader<'a>> {
+    match color {
+        color::Color::Rgba(rgba) => Ok(Shader::SolidColor((*rgba).into())),
+        color::Color::Gradient(color::LinearGradient { angle, stops }) => {
+            create_linear_gradient(*angle, position, size, corner, stops.clone())
+        }
+    }
+}
+
 pub fn create_linear_gradient<'a>(
     mut angle: f32,
     position: Position,
@@ -92,5 +106,5 @@ pub fn create_linear_gradient<'a>(
         tiny_skia::SpreadMode::Pad,
         Transform::identity(),
     )
-    .map_or(Err(make_error("create linear gradient fail!")), |v| Ok(v))
+    .ok_or(make_error("create linear gradient fail!"))
 }
@@ -1,36 +1,248 @@
+use std::f32::consts::PI;
+
 use serde::Deserialize;
-use tiny_skia::{FillRule, Paint, Path, PathBuilder, Pixmap, Shader, Transform};
+use tiny_skia::{
+    BlendMode, FillRule, FilterQuality, Paint, Path, PathBuilder, Pixmap, PixmapPaint, Rect,
+    Shader, Stroke, Transform,
+};
 
 use crate::{
-    color,
-    drawing::shader::{self, create_linear_gradient},
-    effects,
-    metrics::*,
-    utils::{self, make_error, AppResult},
+    color, drawing::shader::create_shader, effects, empty_pixmap, empty_rect, merge_pixmap,
+    metrics::*, paint, utils::AppResult, xywh_rect,
 };
 
+use super::{
+    container::Corner, Align, Draw, DrawResult, GraphicProps, Layout, Measure, PositionMode,
+    VerticalAlign,
+};
+
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+pub enum ArcMode {
+    #[default]
+    Stroke,
+    Fill,
+}
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct Arc {
+    #[serde(flatten)]
+    pub common: GraphicProps,
+    /// outer radius, the arc is laid out as a `2 * radius` square
     pub radius: f32,
-    pub color: color::Rgba,
-    // pub shadow: Option<effects::BoxShadow>,
-    pub position: Position,
+    pub color: color::Color,
+    pub shadow: Option<effects::DropShadow>,
+    pub position: Option<Position<Length>>,
+    pub align: Option<Align>,
+    pub vertical_align: Option<VerticalAlign>,
+    /// stroke width, ignored in `ArcMode::Fill`
     pub width: f32,
+    /// degrees, 0 points to 12 o'clock and angles grow clockwise
+    pub start_angle: Option<f32>,
+    pub end_angle: Option<f32>,
+    pub mode: Option<ArcMode>,
+    #[serde(skip)]
+    pos_bounds: Option<Rect>,
+    #[serde(skip)]
+    layout_bounds: Option<Rect>,
+    #[serde(skip)]
+    resolved_bounds: Option<Rect>,
 }
 
 impl Default for Arc {
     fn default() -> Self {
         Self {
+            common: GraphicProps::default(),
             radius: 0.,
-            color: color::Rgba(0, 0, 0, 255),
-            position: Position::default(),
+            color: color::Color::Rgba(color::Rgba(0, 0, 0, 255)),
+            shadow: None,
+            position: None,
+            align: None,
+            vertical_align: None,
             width: 1.,
+            start_angle: None,
+            end_angle: None,
+            mode: None,
+            pos_bounds: None,
+            layout_bounds: None,
+            resolved_bounds: None,
+        }
+    }
+}
+
+impl Draw for Arc {
+    fn draw(&mut self, mut pixmap: Pixmap) -> AppResult<DrawResult> {
+        let bounds = self.bounds();
+        let path = match self.path() {
+            Some(path) => path,
+            None => return Ok(DrawResult(pixmap, bounds)),
+        };
+        let paint = self.paint()?;
+        let mut arc_pixmap = empty_pixmap!(bounds.right().max(1.), bounds.bottom().max(1.));
+        match self.mode.unwrap_or_default() {
+            ArcMode::Stroke => arc_pixmap.stroke_path(
+                &path,
+                &paint,
+                &Stroke {
+                    width: self.width,
+                    ..Stroke::default()
+                },
+                Transform::identity(),
+                None,
+            ),
+            ArcMode::Fill => arc_pixmap.fill_path(
+                &path,
+                &paint,
+                FillRule::Winding,
+                Transform::identity(),
+                None,
+            ),
+        };
+        if let Some(shadow) = self.shadow {
+            arc_pixmap = shadow.draw(arc_pixmap)?;
         }
+        Ok(DrawResult(merge_pixmap!(pixmap, arc_pixmap), bounds))
+    }
+}
+
+impl Layout for Arc {
+    fn layout(
+        &mut self,
+        pos_bounds: Rect,
+        layout_bounds: Rect,
+        viewport: Viewport,
+    ) -> AppResult<Rect> {
+        self.common.viewport = viewport;
+        self.pos_bounds = Some(pos_bounds);
+        self.layout_bounds = Some(layout_bounds);
+        self.resolved_bounds = Some(self.bounds());
+        Ok(self.bounds())
+    }
+}
+
+impl Measure for Arc {
+    fn measure(
+        &mut self,
+        pos_bounds: Rect,
+        layout_bounds: Rect,
+        viewport: Viewport,
+    ) -> AppResult<Size> {
+        self.common.viewport = viewport;
+        let d = self.radius.max(0.) * 2.;
+        Ok((d, d).into())
     }
 }
 
 impl Arc {
-    pub fn draw(&self) -> AppResult {
-        todo!()
+    /// rect resolved by the last layout
+    pub fn resolved_bounds(&self) -> Option<Rect> {
+        self.resolved_bounds
+    }
+    pub fn paint(&self) -> AppResult<Paint<'_>> {
+        let d = self.radius * 2.;
+        let mut paint = paint!();
+        paint.shader = create_shader(
+            &self.color,
+            (self.x(), self.y()).into(),
+            (d, d).into(),
+            Corner::default(),
+        )?;
+        Ok(paint)
+    }
+
+    /// `None` when the sweep is empty and there is nothing to draw
+    pub fn path(&self) -> Option<Path> {
+        let start = self.start_angle.unwrap_or(0.) / 180. * PI;
+        let end = self.end_angle.unwrap_or(360.) / 180. * PI;
+        let sweep = (end - start).clamp(-2. * PI, 2. * PI);
+        if sweep == 0. || self.radius <= 0. {
+            return None;
+        }
+        let mode = self.mode.unwrap_or_default();
+        let r = match mode {
+            ArcMode::Stroke => self.radius - self.width / 2.,
+            ArcMode::Fill => self.radius,
+        };
+        let (cx, cy) = (self.x() + self.radius, self.y() + self.radius);
+        let point = |angle: f32| (cx + r * angle.sin(), cy - r * angle.cos());
+        let full = sweep.abs() >= 2. * PI;
+        let mut pb = PathBuilder::new();
+        let (x, y) = point(start);
+        if let (ArcMode::Fill, false) = (mode, full) {
+            pb.move_to(cx, cy);
+            pb.line_to(x, y);
+        } else {
+            pb.move_to(x, y);
+        }
+        // split the sweep into segments of at most 90 degrees, each of them
+        // approximated by a cubic bezier curve
+        let segments = (sweep.abs() / (PI / 2.)).ceil();
+        let step = sweep / segments;
+        let k = 4. / 3. * (step / 4.).tan() * r;
+        let mut angle = start;
+        for _ in 0..segments as usize {
+            let next = angle + step;
+            let (x0, y0) = point(angle);
+            let (x1, y1) = point(next);
+            pb.cubic_to(
+                x0 + k * angle.cos(),
+                y0 + k * angle.sin(),
+                x1 - k * next.cos(),
+                y1 - k * next.sin(),
+                x1,
+                y1,
+            );
+            angle = next;
+        }
+        if full || matches!(mode, ArcMode::Fill) {
+            pb.close();
+        }
+        pb.finish()
+    }
+
+    pub fn x(&self) -> f32 {
+        let d = self.radius * 2.;
+        if let Some(x) = self.position().and_then(|position| position.x) {
+            return self
+                .align
+                .unwrap_or_default()
+                .x(x, d, &self.layout_bounds());
+        }
+        if let Some(right) = self.position().and_then(|position| position.right) {
+            return Align::Right.x(right, d, &self.layout_bounds());
+        }
+        if let Some(ref align) = self.align {
+            return align.x(0., d, &self.layout_bounds());
+        }
+        self.pos_bounds().left()
+    }
+    pub fn y(&self) -> f32 {
+        let d = self.radius * 2.;
+        if let Some(y) = self.position().and_then(|position| position.y) {
+            return self
+                .vertical_align
+                .unwrap_or_default()
+                .y(y, d, &self.layout_bounds());
+        }
+        if let Some(bottom) = self.position().and_then(|position| position.bottom) {
+            return VerticalAlign::Bottom.y(bottom, d, &self.layout_bounds());
+        }
+        if let Some(ref vertical_align) = self.vertical_align {
+            return vertical_align.y(0., d, &self.layout_bounds());
+        }
+        self.pos_bounds().top()
+    }
+    pub fn position(&self) -> Option<Position> {
+        self.position
+            .map(|position| position.resolve(&self.layout_bounds(), self.common.viewport))
+    }
+    fn bounds(&self) -> Rect {
+        let d = self.radius.max(0.) * 2.;
+        xywh_rect!(self.x(), self.y(), d, d)
+    }
+    fn pos_bounds(&self) -> Rect {
+        self.pos_bounds.unwrap_or(empty_rect!())
+    }
+    fn layout_bounds(&self) -> Rect {
+        self.layout_bounds.unwrap_or(empty_rect!())
     }
 }
@@ -7,79 +7,129 @@ use wasm_bindgen_test::console_log;
 
 use crate::{
     color,
-    drawing::shader::{self, create_linear_gradient},
+    drawing::{path::rounded_rect, shader::create_shader},
     effects, empty_pixmap, empty_rect, expand_pixmap, lt_rect, ltrb_rect, merge_pixmap,
     metrics::*,
     paint, rgba_paint,
     utils::{self, make_error, AppResult, Union},
+    xywh_rect,
 };
 
-use super::{Align, Draw, DrawResult, Graphic};
+use super::{
+    flex::{self, CrossAlign, Direction, FlexItem, Justify},
+    grid::{self, Cell, Grid, GridLine, Track},
+    Align, Draw, DrawResult, Graphic, GraphicProps, Layout, Measure, PositionMode, VerticalAlign,
+};
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct Container {
-    pub corner: Option<Corner>,
+    #[serde(flatten)]
+    pub common: GraphicProps,
+    pub corner: Option<Corner<Length>>,
     pub color: Option<color::Color>,
     pub shadow: Option<effects::BoxShadow>,
-    pub position: Option<Position>,
-    pub size: Option<Size>,
+    pub position: Option<Position<Length>>,
+    pub size: Option<Size<Length>>,
+    pub min_width: Option<Length>,
+    pub max_width: Option<Length>,
+    pub min_height: Option<Length>,
+    pub max_height: Option<Length>,
+    /// width divided by height, the missing dimension is derived from the other one
+    pub aspect_ratio: Option<f32>,
     pub border: Option<Border>,
     pub children: Option<Vec<Graphic>>,
-    pub padding: Option<Padding>,
+    pub padding: Option<Padding<Length>>,
     pub align: Option<Align>,
+    pub vertical_align: Option<VerticalAlign>,
+    /// main axis the children flow along, `Column` by default
+    pub direction: Option<Direction>,
+    /// space between two adjacent children on the main axis
+    pub gap: Option<f32>,
+    pub justify: Option<Justify>,
+    pub align_items: Option<CrossAlign>,
+    /// children which overflow the main axis start a new line
+    #[serde(default)]
+    pub wrap: bool,
+    pub flex_grow: Option<f32>,
+    pub flex_shrink: Option<f32>,
+    pub flex_basis: Option<f32>,
+    /// overrides `align_items` of the parent for this container
+    pub align_self: Option<CrossAlign>,
+    /// lays the children out in a grid instead of `direction`
+    pub grid: Option<Grid>,
     #[serde(default)]
     pub clip: bool,
+    /// size assigned by the flex layout of the parent, takes precedence over `size`
+    #[serde(skip)]
+    resolved_size: Option<Size>,
+    #[serde(skip)]
+    resolved_bounds: Option<Rect>,
     #[serde(skip)]
     children_bounds: Option<Rect>,
     #[serde(skip)]
     pos_bounds: Option<Rect>,
     #[serde(skip)]
     layout_bounds: Option<Rect>,
+    /// content sizes measured since the last layout, see `content_size`
+    #[serde(skip)]
+    measured: Vec<(ContentKey, Size)>,
 }
 
+/// size of the children bounds, the height assigned by the parent and the
+/// viewport, which is all the flow of the content depends on
+type ContentKey = (f32, f32, Option<f32>, Viewport);
+
 impl Default for Container {
     fn default() -> Self {
         Container {
+            common: GraphicProps::default(),
             corner: None,
             color: None,
             shadow: None,
             position: Some((0., 0.).into()),
             size: Some((0., 0.).into()),
+            min_width: None,
+            max_width: None,
+            min_height: None,
+            max_height: None,
+            aspect_ratio: None,
             border: None,
             children: None,
             padding: None,
             align: None,
+            vertical_align: None,
+            direction: None,
+            gap: None,
+            justify: None,
+            align_items: None,
+            wrap: false,
+            flex_grow: None,
+            flex_shrink: None,
+            flex_basis: None,
+            align_self: None,
+            grid: None,
+            resolved_size: None,
+            resolved_bounds: None,
             children_bounds: None,
             clip: false,
             pos_bounds: None,
             layout_bounds: None,
+            measured: Vec::new(),
         }
     }
 }
 
 impl Draw for Container {
-    fn draw(
-        &mut self,
-        mut pixmap: Pixmap,
-        pos_bounds: Rect,
-        layout_bounds: Rect,
-    ) -> AppResult<DrawResult> {
-        self.pos_bounds = Some(pos_bounds);
-        self.layout_bounds = Some(layout_bounds);
-        let mut children_bounds = self.children_layout_bounds();
+    fn draw(&mut self, mut pixmap: Pixmap) -> AppResult<DrawResult> {
         let mut children_pixmap = empty_pixmap!(pixmap.width(), pixmap.height());
-        if let Some(children) = self.children.take() {
-            let layout_bounds = self.children_layout_bounds();
-            let mut pos_bounds = layout_bounds.clone();
-            for mut child in children {
-                let DrawResult(pixmap, bounds) =
-                    child.draw(children_pixmap, pos_bounds, layout_bounds)?;
+        if let Some(ref mut children) = self.children {
+            let mut children: Vec<_> = children.iter_mut().collect();
+            children.sort_by_key(|child| child.props().z_index());
+            for child in children {
+                let DrawResult(pixmap, _) = child.draw(children_pixmap)?;
                 children_pixmap = pixmap;
-                pos_bounds = lt_rect!(bounds.right(), bounds.bottom());
-                children_bounds = children_bounds.union(&bounds);
             }
         }
-        self.children_bounds = Some(children_bounds);
         let path = self.path()?;
         if let Some(shadow) = self.shadow {
             pixmap = shadow.draw(pixmap, &path)?;
@@ -114,89 +164,434 @@ impl Draw for Container {
     }
 }
 
-impl Container {
-    pub fn paint(&self) -> AppResult<Paint> {
-        let mut paint = paint!();
-        paint.shader = match self.color() {
-            color::Color::Rgba(color::Rgba(r, g, b, a)) => {
-                Shader::SolidColor(tiny_skia::Color::from_rgba8(r, g, b, a))
+impl Layout for Container {
+    fn layout(
+        &mut self,
+        pos_bounds: Rect,
+        layout_bounds: Rect,
+        viewport: Viewport,
+    ) -> AppResult<Rect> {
+        self.common.viewport = viewport;
+        self.pos_bounds = Some(pos_bounds);
+        self.layout_bounds = Some(layout_bounds);
+        self.children_bounds = None;
+        self.measured.clear();
+        if let Some(mut children) = self.children.take() {
+            let children_bounds = self.layout_children(&mut children);
+            self.children = Some(children);
+            self.children_bounds = Some(children_bounds?);
+        }
+        let bounds = xywh_rect!(self.x(), self.y(), self.width(), self.height());
+        self.resolved_bounds = Some(bounds);
+        Ok(bounds)
+    }
+}
+
+impl Measure for Container {
+    fn measure(
+        &mut self,
+        pos_bounds: Rect,
+        layout_bounds: Rect,
+        viewport: Viewport,
+    ) -> AppResult<Size> {
+        self.common.viewport = viewport;
+        self.pos_bounds = Some(pos_bounds);
+        self.layout_bounds = Some(layout_bounds);
+        self.children_bounds = None;
+        let height = match self.fixed_height() {
+            Some(h) => h,
+            None => {
+                let p = self.padding();
+                let h = self.content_size()?.height() + p.top() + p.bottom();
+                self.clamp_height(h)
             }
-            color::Color::Gradient(color::LinearGradient { angle, stops }) => {
-                let x = self.x();
-                let y = self.y();
-                let w = self.width();
-                let h = self.height();
-
-                create_linear_gradient(
-                    angle,
-                    (x, y).into(),
-                    (w, h).into(),
-                    self.corner(),
-                    stops.clone(),
-                )?
+        };
+        Ok((self.width(), height).into())
+    }
+}
+
+#[cfg(test)]
+thread_local! {
+    /// flows resolved on the thread, nesting must not multiply them
+    pub static FLOWS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// pos_bounds and layout_bounds of every child, and the size they take together
+struct Flow {
+    bounds: Vec<(Rect, Rect)>,
+    content: Size,
+}
+
+impl Container {
+    /// rect resolved by the last layout
+    pub fn resolved_bounds(&self) -> Option<Rect> {
+        self.resolved_bounds
+    }
+    /// the height of an auto sized container is only known once its content
+    /// is, so the flow is resolved first to size the container and then again
+    /// within the final bounds before the children are laid out
+    fn layout_children(&mut self, children: &mut [Graphic]) -> AppResult<Rect> {
+        let bounds = self.children_layout_bounds();
+        if self.fixed_height().is_none() {
+            let content = self.flow(children, bounds)?.content;
+            self.children_bounds = Some(xywh_rect!(
+                bounds.left(),
+                bounds.top(),
+                content.width(),
+                content.height()
+            ));
+        }
+        let bounds = self.children_layout_bounds();
+        let flow = self.flow(children, bounds)?;
+        let mut children_bounds = self
+            .children_bounds
+            .unwrap_or(lt_rect!(bounds.left(), bounds.top()));
+        for (child, (pos_bounds, layout_bounds)) in children.iter_mut().zip(flow.bounds) {
+            let bounds = child.layout(pos_bounds, layout_bounds, self.common.viewport)?;
+            if child.props().position_mode() == PositionMode::Flow {
+                children_bounds = children_bounds.union(&bounds);
             }
+        }
+        Ok(children_bounds)
+    }
+
+    /// lays the children out before anything is drawn, absolutely positioned
+    /// children are left out and placed at the start of `bounds`
+    fn flow(&self, children: &mut [Graphic], bounds: Rect) -> AppResult<Flow> {
+        #[cfg(test)]
+        FLOWS.with(|flows| flows.set(flows.get() + 1));
+        let mut child_bounds =
+            vec![(lt_rect!(bounds.left(), bounds.top()), bounds); children.len()];
+        let (indices, mut flowing): (Vec<_>, Vec<_>) = children
+            .iter_mut()
+            .enumerate()
+            .filter(|(_, child)| child.props().position_mode() == PositionMode::Flow)
+            .unzip();
+        let flow = match self.grid {
+            Some(ref grid) => self.grid_flow(grid, &mut flowing, bounds)?,
+            None => self.flex_flow(&mut flowing, bounds)?,
         };
-        Ok(paint)
+        for (i, bounds) in indices.into_iter().zip(flow.bounds) {
+            child_bounds[i] = bounds;
+        }
+        Ok(Flow {
+            bounds: child_bounds,
+            content: flow.content,
+        })
     }
 
-    pub fn path(&self) -> AppResult<Path> {
-        let x = self.x();
-        let y = self.y();
-        let w = self.width();
-        let h = self.height();
-        let mut pb = PathBuilder::new();
-        let Corner(c0, c1, c2, c3) = self.corner();
-        let (mid_x, mid_y) = (x + w / 2., y + h / 2.);
-        let (max_x, max_y) = (x + w, y + h);
-        pb.move_to(x, mid_y);
-        if c0 == 0. {
-            pb.line_to(x, y);
-        } else {
-            let offset = 0.45 * c0;
-            pb.line_to(x, y + c0);
-            pb.cubic_to(x, y + offset, x + offset, y, x + c0, y);
+    /// lays the children out along `direction`, each line is resolved with the
+    /// flex properties of its children
+    fn flex_flow(&self, children: &mut [&mut Graphic], bounds: Rect) -> AppResult<Flow> {
+        let direction = self.direction.unwrap_or_default();
+        let gap = self.gap.unwrap_or(0.);
+        let origin = lt_rect!(bounds.left(), bounds.top());
+        // margins do not collapse, they are part of the outer size of an item
+        let margins: Vec<_> = children
+            .iter()
+            .map(|child| child.props().margin(&bounds, self.common.viewport))
+            .collect();
+        let items = children
+            .iter_mut()
+            .zip(&margins)
+            .map(|(child, margin)| {
+                let item = flex_item(child, direction, origin, bounds, self.common.viewport)?;
+                Ok(FlexItem {
+                    basis: item.basis + direction.main(&margin.size()),
+                    ..item
+                })
+            })
+            .collect::<AppResult<Vec<_>>>()?;
+        let (definite_main, definite_cross) = match (direction, self.fixed_height()) {
+            (Direction::Row, h) => (Some(bounds.width()), h.map(|_| bounds.height())),
+            (Direction::Column, h) => (h.map(|_| bounds.height()), Some(bounds.width())),
+        };
+        let lines = match definite_main {
+            Some(main) if self.wrap => flex::wrap_lines(&items, main, gap),
+            _ => flex::wrap_lines(&items, f32::INFINITY, gap),
+        };
+        let single_line = lines.len() == 1;
+        let align_items = self.align_items.unwrap_or_default();
+        let mut child_bounds = vec![(origin, bounds); items.len()];
+        let mut content_main = 0_f32;
+        let mut content_cross = 0.;
+        let mut cross_start = 0.;
+        for (i, line) in lines.into_iter().enumerate() {
+            let line_items = &items[line.clone()];
+            let mains = match definite_main {
+                Some(main) => flex::resolve(line_items, main, gap),
+                None => line_items.iter().map(|item| item.basis).collect(),
+            };
+            let mut sizes = Vec::with_capacity(mains.len());
+            for ((child, main), margin) in children[line.clone()]
+                .iter_mut()
+                .zip(mains)
+                .zip(&margins[line.clone()])
+            {
+                let outer = margin.size();
+                let main = (main - direction.main(&outer)).max(0.);
+                // like a css block, a column child without a width fills the
+                // width left by its margins
+                let cross = match (direction, &*child) {
+                    (Direction::Column, Graphic::Container(container))
+                        if outer.width() > 0. && container.definite_width().is_none() =>
+                    {
+                        Some((bounds.width() - outer.width()).max(0.))
+                    }
+                    _ => None,
+                };
+                resize(child, direction.size(Some(main), cross));
+                let size = child.measure(origin, bounds, self.common.viewport)?;
+                sizes.push((size.width() + outer.width(), size.height() + outer.height()).into());
+            }
+            let line_main = direction.content_main(&sizes, gap);
+            let line_content = direction.content_cross(&sizes);
+            let line_cross = match definite_cross {
+                Some(cross) if single_line => cross,
+                _ => line_content,
+            };
+            let (mut main, spacing) = self.justify.unwrap_or_default().distribute(
+                definite_main.unwrap_or(line_main) - line_main,
+                gap,
+                sizes.len(),
+            );
+            for ((j, size), item) in line.zip(sizes).zip(line_items) {
+                let align = item.align_self.unwrap_or(align_items);
+                let margin = margins[j];
+                let cross_size = match align {
+                    CrossAlign::Stretch if auto_cross(children[j], direction) => {
+                        let cross = (line_cross - direction.cross(&margin.size())).max(0.);
+                        resize(children[j], direction.size(None, Some(cross)));
+                        line_cross
+                    }
+                    _ => direction.cross(&size),
+                };
+                let cross = cross_start + align.offset(line_cross - cross_size);
+                let offset = margin.offset();
+                child_bounds[j] = (
+                    direction.place(
+                        &bounds,
+                        main + direction.main(&offset),
+                        cross + direction.cross(&offset),
+                    ),
+                    bounds,
+                );
+                main += direction.main(&size) + spacing;
+            }
+            content_main = content_main.max(line_main);
+            content_cross += if i == 0 {
+                line_content
+            } else {
+                gap + line_content
+            };
+            cross_start += line_cross + gap;
         }
-        pb.line_to(mid_x, y);
-        if c1 == 0. {
-            pb.line_to(max_x, y);
-        } else {
-            let offset = 0.45 * c1;
-            pb.line_to(max_x - c1, y);
-            pb.cubic_to(max_x - offset, y, max_x, y + offset, max_x, y + c1);
+        Ok(Flow {
+            bounds: child_bounds,
+            content: direction.size(Some(content_main), Some(content_cross)),
+        })
+    }
+
+    /// children are laid out in the cells of `grid`, a cell is the layout_bounds
+    /// of its child so that `align` of the child applies within the cell
+    fn grid_flow(
+        &self,
+        grid: &Grid,
+        children: &mut [&mut Graphic],
+        bounds: Rect,
+    ) -> AppResult<Flow> {
+        let column_gap = grid.column_gap.or(self.gap).unwrap_or(0.);
+        let row_gap = grid.row_gap.or(self.gap).unwrap_or(0.);
+        let columns = match grid.columns.len() {
+            0 => vec![Track::Auto],
+            _ => grid.columns.clone(),
+        };
+        let lines: Vec<_> = children.iter_mut().map(|child| grid_lines(child)).collect();
+        let margins: Vec<_> = children
+            .iter()
+            .map(|child| child.props().margin(&bounds, self.common.viewport))
+            .collect();
+        let cells = grid::place(&lines, columns.len());
+        let origin = lt_rect!(bounds.left(), bounds.top());
+
+        let mut content = vec![0_f32; columns.len()];
+        for ((child, cell), margin) in children.iter_mut().zip(&cells).zip(&margins) {
+            if cell.column_span == 1 && matches!(columns[cell.column], Track::Auto) {
+                let w =
+                    fit_width(child, origin, bounds, self.common.viewport)? + margin.size().width();
+                content[cell.column] = content[cell.column].max(w);
+            }
         }
-        pb.line_to(max_x, mid_y);
-        if c2 == 0. {
-            pb.line_to(max_x, max_y);
-        } else {
-            let offset = 0.45 * c2;
-            pb.line_to(max_x, max_y - c2);
-            pb.cubic_to(
-                max_x,
-                max_y - offset,
-                max_x - offset,
-                max_y,
-                max_x - c2,
-                max_y,
+        let widths = grid::resolve_tracks(&columns, &content, Some(bounds.width()), column_gap);
+        let (xs, content_w) = grid::offsets(&widths, column_gap);
+
+        let row_count = cells
+            .iter()
+            .map(|cell| cell.row + cell.row_span)
+            .chain(grid.rows.as_ref().map(|rows| rows.len()))
+            .max()
+            .unwrap_or(0);
+        let rows: Vec<_> = (0..row_count)
+            .map(|i| {
+                grid.rows
+                    .as_ref()
+                    .and_then(|rows| rows.get(i).copied())
+                    .unwrap_or(Track::Auto)
+            })
+            .collect();
+        let cell_width =
+            |cell: &Cell| grid::span(&widths, cell.column, cell.column_span, column_gap);
+        let mut content = vec![0_f32; row_count];
+        for ((child, cell), margin) in children.iter_mut().zip(&cells).zip(&margins) {
+            if cell.row_span == 1 {
+                let outer = margin.size();
+                let x = bounds.left() + xs[cell.column] + margin.left();
+                let w = (cell_width(cell) - outer.width()).max(0.);
+                let layout_bounds = xywh_rect!(x, bounds.top(), w, 0.);
+                let size = child.measure(
+                    lt_rect!(x, bounds.top()),
+                    layout_bounds,
+                    self.common.viewport,
+                )?;
+                content[cell.row] = content[cell.row].max(size.height() + outer.height());
+            }
+        }
+        let available_h = self.fixed_height().map(|_| bounds.height());
+        let heights = grid::resolve_tracks(&rows, &content, available_h, row_gap);
+        let (ys, content_h) = grid::offsets(&heights, row_gap);
+
+        let mut child_bounds = Vec::with_capacity(cells.len());
+        for ((child, cell), margin) in children.iter_mut().zip(&cells).zip(&margins) {
+            let outer = margin.size();
+            let h =
+                (grid::span(&heights, cell.row, cell.row_span, row_gap) - outer.height()).max(0.);
+            let cell_bounds = xywh_rect!(
+                bounds.left() + xs[cell.column] + margin.left(),
+                bounds.top() + ys[cell.row] + margin.top(),
+                (cell_width(cell) - outer.width()).max(0.),
+                h
             );
+            // like css, containers without a height are stretched to their cell
+            if let Graphic::Container(container) = child {
+                if container.fixed_height().is_none() {
+                    resize(
+                        child,
+                        Size {
+                            width: None,
+                            height: Some(h),
+                        },
+                    );
+                }
+            }
+            child_bounds.push((lt_rect!(cell_bounds.left(), cell_bounds.top()), cell_bounds));
         }
-        pb.line_to(mid_x, max_y);
-        if c3 == 0. {
-            pb.line_to(x, max_y);
-        } else {
-            let offset = 0.45 * c3;
-            pb.line_to(x + c3, max_y);
-            pb.cubic_to(x + offset, max_y, x, max_y - offset, x, max_y - c3);
+        Ok(Flow {
+            bounds: child_bounds,
+            content: (content_w, content_h).into(),
+        })
+    }
+
+    /// a parent measures its children several times before they are laid
+    /// out, so the content is only flowed once per constraint until then,
+    /// otherwise every level of nesting would multiply the work
+    fn content_size(&mut self) -> AppResult<Size> {
+        let bounds = self.children_layout_bounds();
+        let key = (
+            bounds.width(),
+            bounds.height(),
+            self.fixed_height(),
+            self.common.viewport,
+        );
+        if let Some((_, size)) = self.measured.iter().find(|(k, _)| *k == key) {
+            return Ok(*size);
+        }
+        let size = match self.children.take() {
+            Some(mut children) => {
+                let flow = self.flow(&mut children, bounds);
+                self.children = Some(children);
+                flow?.content
+            }
+            None => Size::default(),
+        };
+        self.measured.push((key, size));
+        Ok(size)
+    }
+
+    /// width fitted to the content when the container has no width,
+    /// instead of taking the whole layout width
+    fn fit_width(
+        &mut self,
+        pos_bounds: Rect,
+        layout_bounds: Rect,
+        viewport: Viewport,
+    ) -> AppResult<f32> {
+        let size = self.measure(pos_bounds, layout_bounds, viewport)?;
+        if self.definite_width().is_some() {
+            return Ok(size.width());
         }
-        pb.line_to(x, mid_y);
-        pb.close();
-        pb.finish()
-            .map_or(Err(make_error("path generation fail!")), |v| Ok(v))
+        let p = self.padding();
+        let w = self.content_size()?.width() + p.left() + p.right();
+        Ok(self.clamp_width(w))
     }
 
+    fn flex_item(
+        &mut self,
+        direction: Direction,
+        pos_bounds: Rect,
+        layout_bounds: Rect,
+        viewport: Viewport,
+    ) -> AppResult<FlexItem> {
+        self.common.viewport = viewport;
+        self.resolved_size = None;
+        self.layout_bounds = Some(layout_bounds);
+        let fixed = match direction {
+            Direction::Row => self.definite_width(),
+            Direction::Column => self.fixed_height(),
+        };
+        let basis = match (self.flex_basis.or(fixed), direction) {
+            (Some(basis), _) => basis,
+            // like a css flex item, the width is fitted to the content
+            (None, Direction::Row) => self.fit_width(pos_bounds, layout_bounds, viewport)?,
+            (None, Direction::Column) => {
+                self.measure(pos_bounds, layout_bounds, viewport)?.height()
+            }
+        };
+        Ok(FlexItem {
+            basis,
+            grow: self.flex_grow.unwrap_or(0.),
+            shrink: self.flex_shrink.unwrap_or(1.),
+            align_self: self.align_self,
+        })
+    }
+
+    pub fn paint(&self) -> AppResult<Paint<'_>> {
+        let mut paint = paint!();
+        paint.shader = create_shader(
+            &self.color(),
+            (self.x(), self.y()).into(),
+            (self.width(), self.height()).into(),
+            self.corner(),
+        )?;
+        Ok(paint)
+    }
+
+    pub fn path(&self) -> AppResult<Path> {
+        rounded_rect(
+            self.x(),
+            self.y(),
+            self.width(),
+            self.height(),
+            self.corner(),
+        )
+    }
+
+    /// percentages refer to the shorter side of the container
     pub fn corner(&self) -> Corner {
+        let (w, h) = (self.width(), self.height());
         self.corner
+            .map(|corner| corner.resolve(w.min(h), self.common.viewport))
             .unwrap_or_default()
-            .get_fitted(&(self.width(), self.height()).into())
+            .get_fitted(&(w, h).into())
     }
 
     pub fn color(&self) -> color::Color {
@@ -208,14 +603,14 @@ impl Container {
     }
 
     pub fn x(&self) -> f32 {
-        if let Some(ref position) = self.position {
-            if let Some(ref x) = position.x {
-                return self.align.or(Some(Align::Left)).unwrap().x(
-                    *x,
-                    self.width(),
-                    &self.layout_bounds(),
-                );
-            }
+        if let Some(x) = self.position().and_then(|position| position.x) {
+            return self
+                .align
+                .unwrap_or_default()
+                .x(x, self.width(), &self.layout_bounds());
+        }
+        if let Some(right) = self.position().and_then(|position| position.right) {
+            return Align::Right.x(right, self.width(), &self.layout_bounds());
         }
         if let Some(ref align) = self.align {
             return align.x(0., self.width(), &self.layout_bounds());
@@ -223,32 +618,94 @@ impl Container {
         self.pos_bounds().left()
     }
     pub fn y(&self) -> f32 {
-        if let Some(ref position) = self.position {
-            if let Some(ref y) = position.y {
-                return *y + self.layout_bounds().top();
-            }
+        if let Some(y) = self.position().and_then(|position| position.y) {
+            return self.vertical_align.unwrap_or_default().y(
+                y,
+                self.height(),
+                &self.layout_bounds(),
+            );
+        }
+        if let Some(bottom) = self.position().and_then(|position| position.bottom) {
+            return VerticalAlign::Bottom.y(bottom, self.height(), &self.layout_bounds());
+        }
+        if let Some(ref vertical_align) = self.vertical_align {
+            return vertical_align.y(0., self.height(), &self.layout_bounds());
         }
         self.pos_bounds().top()
     }
+    pub fn position(&self) -> Option<Position> {
+        self.position
+            .map(|position| position.resolve(&self.layout_bounds(), self.common.viewport))
+    }
+    pub fn size(&self) -> Option<Size> {
+        self.size
+            .map(|size| size.resolve(&self.layout_bounds(), self.common.viewport))
+    }
     pub fn padding(&self) -> Padding {
-        self.padding.unwrap_or_default()
+        self.padding
+            .map(|padding| padding.resolve(&self.layout_bounds(), self.common.viewport))
+            .unwrap_or_default()
     }
     pub fn width(&self) -> f32 {
-        if let Some(ref size) = self.size {
-            if let Some(ref w) = size.width {
-                return *w;
-            }
-        }
-        self.layout_bounds().width()
+        self.definite_width()
+            .unwrap_or_else(|| self.clamp_width(self.layout_bounds().width()))
     }
     pub fn height(&self) -> f32 {
-        if let Some(ref size) = self.size {
-            if let Some(ref h) = size.height {
-                return *h;
-            }
+        if let Some(h) = self.fixed_height() {
+            return h;
         }
         let p = self.padding();
-        self.children_bounds().height() + p.top() + p.bottom()
+        self.clamp_height(self.children_bounds().height() + p.top() + p.bottom())
+    }
+    /// size assigned by the parent, falling back to `size`
+    fn base_size(&self) -> Size {
+        let size = self.size().unwrap_or(Size {
+            width: None,
+            height: None,
+        });
+        let resolved = self.resolved_size.unwrap_or(size);
+        Size {
+            width: resolved.width.or(size.width),
+            height: resolved.height.or(size.height),
+        }
+    }
+    /// width which does not depend on the layout bounds
+    fn definite_width(&self) -> Option<f32> {
+        let size = self.base_size();
+        let w = match (size.width, size.height, self.aspect_ratio) {
+            (Some(w), _, _) => w,
+            (None, Some(h), Some(ratio)) if ratio > 0. => self.clamp_height(h) * ratio,
+            _ => return None,
+        };
+        Some(self.clamp_width(w))
+    }
+    fn fixed_height(&self) -> Option<f32> {
+        let h = match (self.base_size().height, self.aspect_ratio) {
+            (Some(h), _) => h,
+            (None, Some(ratio)) if ratio > 0. => self.width() / ratio,
+            _ => return None,
+        };
+        Some(self.clamp_height(h))
+    }
+    fn clamp_width(&self, w: f32) -> f32 {
+        let base = self.layout_bounds().width();
+        let max = self
+            .max_width
+            .map_or(f32::INFINITY, |max| max.resolve(base, self.common.viewport));
+        let min = self
+            .min_width
+            .map_or(0., |min| min.resolve(base, self.common.viewport));
+        w.min(max).max(min)
+    }
+    fn clamp_height(&self, h: f32) -> f32 {
+        let base = self.layout_bounds().height();
+        let max = self
+            .max_height
+            .map_or(f32::INFINITY, |max| max.resolve(base, self.common.viewport));
+        let min = self
+            .min_height
+            .map_or(0., |min| min.resolve(base, self.common.viewport));
+        h.min(max).max(min)
     }
     fn layout_bounds(&self) -> Rect {
         self.layout_bounds.unwrap_or(empty_rect!())
@@ -271,8 +728,71 @@ impl Container {
     }
 }
 
+fn flex_item(
+    child: &mut Graphic,
+    direction: Direction,
+    pos_bounds: Rect,
+    layout_bounds: Rect,
+    viewport: Viewport,
+) -> AppResult<FlexItem> {
+    match child {
+        Graphic::Container(container) => {
+            container.flex_item(direction, pos_bounds, layout_bounds, viewport)
+        }
+        child => Ok(FlexItem {
+            basis: direction.main(&child.measure(pos_bounds, layout_bounds, viewport)?),
+            ..FlexItem::default()
+        }),
+    }
+}
+
+fn fit_width(
+    child: &mut Graphic,
+    pos_bounds: Rect,
+    layout_bounds: Rect,
+    viewport: Viewport,
+) -> AppResult<f32> {
+    match child {
+        Graphic::Container(container) => container.fit_width(pos_bounds, layout_bounds, viewport),
+        child => Ok(child.measure(pos_bounds, layout_bounds, viewport)?.width()),
+    }
+}
+
+fn grid_lines(child: &mut Graphic) -> (GridLine, GridLine) {
+    if let Graphic::Container(container) = child {
+        container.resolved_size = None;
+    }
+    let props = child.props();
+    (
+        props.grid_column.unwrap_or_default(),
+        props.grid_row.unwrap_or_default(),
+    )
+}
+
+/// like css, only an item without its own cross size is stretched
+fn auto_cross(child: &Graphic, direction: Direction) -> bool {
+    match (child, direction) {
+        (Graphic::Container(container), Direction::Row) => container.fixed_height().is_none(),
+        (Graphic::Container(container), Direction::Column) => container.definite_width().is_none(),
+        _ => false,
+    }
+}
+
+fn resize(child: &mut Graphic, size: Size) {
+    if let Graphic::Container(container) = child {
+        let resolved = container.resolved_size.unwrap_or(Size {
+            width: None,
+            height: None,
+        });
+        container.resolved_size = Some(Size {
+            width: size.width.or(resolved.width),
+            height: size.height.or(resolved.height),
+        });
+    }
+}
+
 #[derive(Deserialize, Debug, Clone, Copy)]
-pub struct Corner(pub f32, pub f32, pub f32, pub f32);
+pub struct Corner<T = f32>(pub T, pub T, pub T, pub T);
 #[derive(Deserialize, Debug, Clone, Copy)]
 pub struct Border {
     pub width: f32,
@@ -302,6 +822,17 @@ impl Default for Corner {
     }
 }
 
+impl Corner<Length> {
+    pub fn resolve(&self, base: f32, viewport: Viewport) -> Corner {
+        Corner(
+            self.0.resolve(base, viewport),
+            self.1.resolve(base, viewport),
+            self.2.resolve(base, viewport),
+            self.3.resolve(base, viewport),
+        )
+    }
+}
+
 impl Corner {
     pub fn get_fitted(&self, Size { width, height }: &Size) -> Self {
         let w = width.unwrap_or(0.);
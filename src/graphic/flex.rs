@@ -0,0 +1,150 @@
+use std::ops::Range;
+
+use serde::Deserialize;
+use tiny_skia::Rect;
+
+use crate::{lt_rect, metrics::*};
+
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+pub enum Direction {
+    Row,
+    #[default]
+    Column,
+}
+
+impl Direction {
+    pub fn main(&self, size: &Size) -> f32 {
+        match self {
+            Direction::Row => size.width(),
+            Direction::Column => size.height(),
+        }
+    }
+    pub fn cross(&self, size: &Size) -> f32 {
+        match self {
+            Direction::Row => size.height(),
+            Direction::Column => size.width(),
+        }
+    }
+    pub fn size(&self, main: Option<f32>, cross: Option<f32>) -> Size {
+        match self {
+            Direction::Row => Size {
+                width: main,
+                height: cross,
+            },
+            Direction::Column => Size {
+                width: cross,
+                height: main,
+            },
+        }
+    }
+    /// top left of a child which is `main` and `cross` away from the start of `bounds`
+    pub fn place(&self, bounds: &Rect, main: f32, cross: f32) -> Rect {
+        match self {
+            Direction::Row => lt_rect!(bounds.left() + main, bounds.top() + cross),
+            Direction::Column => lt_rect!(bounds.left() + cross, bounds.top() + main),
+        }
+    }
+    pub fn content_main(&self, sizes: &[Size], gap: f32) -> f32 {
+        let gaps = gap * sizes.len().saturating_sub(1) as f32;
+        sizes.iter().map(|size| self.main(size)).sum::<f32>() + gaps
+    }
+    pub fn content_cross(&self, sizes: &[Size]) -> f32 {
+        sizes.iter().map(|size| self.cross(size)).fold(0., f32::max)
+    }
+}
+
+/// distribution of the free space on the main axis
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+pub enum Justify {
+    #[default]
+    Start,
+    Center,
+    End,
+    SpaceBetween,
+}
+
+impl Justify {
+    /// returns the offset of the first child and the space between two children
+    pub fn distribute(&self, free: f32, gap: f32, count: usize) -> (f32, f32) {
+        let free = free.max(0.);
+        match self {
+            Justify::Start => (0., gap),
+            Justify::Center => (free / 2., gap),
+            Justify::End => (free, gap),
+            Justify::SpaceBetween if count > 1 => (0., gap + free / (count - 1) as f32),
+            Justify::SpaceBetween => (0., gap),
+        }
+    }
+}
+
+/// alignment on the cross axis
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+pub enum CrossAlign {
+    #[default]
+    Start,
+    Center,
+    End,
+    /// fills the cross size of the line, only containers can be stretched
+    Stretch,
+}
+
+impl CrossAlign {
+    pub fn offset(&self, free: f32) -> f32 {
+        match self {
+            CrossAlign::Start | CrossAlign::Stretch => 0.,
+            CrossAlign::Center => free / 2.,
+            CrossAlign::End => free,
+        }
+    }
+}
+
+/// flex properties of a child, graphics other than `Container` are never resized
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FlexItem {
+    /// main size before free space is distributed
+    pub basis: f32,
+    pub grow: f32,
+    pub shrink: f32,
+    pub align_self: Option<CrossAlign>,
+}
+
+/// resolves the main size of every item of a line
+pub fn resolve(items: &[FlexItem], available: f32, gap: f32) -> Vec<f32> {
+    let gaps = gap * items.len().saturating_sub(1) as f32;
+    let free = available - gaps - items.iter().map(|item| item.basis).sum::<f32>();
+    let grow: f32 = items.iter().map(|item| item.grow).sum();
+    // like css, shrinking is weighted by the basis so that small items are not crushed
+    let shrink: f32 = items.iter().map(|item| item.shrink * item.basis).sum();
+    items
+        .iter()
+        .map(|item| {
+            if free > 0. && grow > 0. {
+                item.basis + free * item.grow / grow
+            } else if free < 0. && shrink > 0. {
+                (item.basis + free * item.shrink * item.basis / shrink).max(0.)
+            } else {
+                item.basis
+            }
+        })
+        .collect()
+}
+
+/// splits items into lines which fit in `available`, a line has at least one item
+pub fn wrap_lines(items: &[FlexItem], available: f32, gap: f32) -> Vec<Range<usize>> {
+    let mut lines = vec![];
+    let mut start = 0;
+    let mut used = 0.;
+    for (i, item) in items.iter().enumerate() {
+        if i > start && used + gap + item.basis > available {
+            lines.push(start..i);
+            start = i;
+        }
+        used = if i == start {
+            item.basis
+        } else {
+            used + gap + item.basis
+        };
+    }
+    lines.push(start..items.len());
+    lines
+}
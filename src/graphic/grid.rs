@@ -0,0 +1,174 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(tag = "type", content = "value")]
+pub enum Track {
+    Px(f32),
+    /// share of the space left by the other tracks
+    Fr(f32),
+    /// fitted to the largest child of the track
+    Auto,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Grid {
+    pub columns: Vec<Track>,
+    /// rows beyond the defined ones are `Track::Auto`
+    pub rows: Option<Vec<Track>>,
+    /// fall back to the `gap` of the container
+    pub column_gap: Option<f32>,
+    pub row_gap: Option<f32>,
+}
+
+/// placement of a child on one axis, like css `grid-column` and `grid-row`
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+pub struct GridLine {
+    /// 1 based line the child starts at, the first track starts at line 1
+    pub start: Option<usize>,
+    pub span: Option<usize>,
+}
+
+impl GridLine {
+    /// 0 based index of the first track of the child
+    fn track(&self) -> Option<usize> {
+        self.start.map(|start| start.saturating_sub(1))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Cell {
+    pub column: usize,
+    pub row: usize,
+    pub column_span: usize,
+    pub row_span: usize,
+}
+
+/// assigns a cell to every child. like css, children with an explicit row and
+/// column are placed first, the others fill the first free cells row by row
+pub fn place(lines: &[(GridLine, GridLine)], columns: usize) -> Vec<Cell> {
+    let mut occupied: Vec<Vec<bool>> = vec![];
+    let is_free = |occupied: &Vec<Vec<bool>>, cell: &Cell| {
+        (cell.row..cell.row + cell.row_span).all(|r| {
+            (cell.column..cell.column + cell.column_span)
+                .all(|c| !occupied.get(r).map(|row| row[c]).unwrap_or(false))
+        })
+    };
+    let mut cursor = (0, 0);
+    let mut cells = vec![None; lines.len()];
+    let explicit =
+        |(column, row): &&(GridLine, GridLine)| column.start.is_some() && row.start.is_some();
+    let order = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, lines)| explicit(lines))
+        .chain(
+            lines
+                .iter()
+                .enumerate()
+                .filter(|(_, lines)| !explicit(lines)),
+        );
+    for (i, (column, row)) in order {
+        let column_span = column.span.unwrap_or(1).clamp(1, columns);
+        let row_span = row.span.unwrap_or(1).max(1);
+        let mut cell = Cell {
+            column: column.track().unwrap_or(0).min(columns - column_span),
+            row: row.track().unwrap_or(0),
+            column_span,
+            row_span,
+        };
+        match (column.start, row.start) {
+            (Some(_), Some(_)) => {}
+            (Some(_), None) => {
+                while !is_free(&occupied, &cell) {
+                    cell.row += 1;
+                }
+            }
+            (None, Some(_)) => {
+                cell.column = 0;
+                while !is_free(&occupied, &cell) {
+                    if cell.column + column_span < columns {
+                        cell.column += 1;
+                    } else {
+                        // the row is full, overflow into the implicit rows below
+                        cell.column = 0;
+                        cell.row += 1;
+                    }
+                }
+            }
+            (None, None) => {
+                (cell.row, cell.column) = cursor;
+                loop {
+                    if cell.column + column_span > columns {
+                        cell.column = 0;
+                        cell.row += 1;
+                    } else if is_free(&occupied, &cell) {
+                        break;
+                    } else {
+                        cell.column += 1;
+                    }
+                }
+                cursor = (cell.row, cell.column + column_span);
+            }
+        }
+        for r in cell.row..cell.row + cell.row_span {
+            if occupied.len() <= r {
+                occupied.resize(r + 1, vec![false; columns]);
+            }
+            occupied[r][cell.column..cell.column + cell.column_span].fill(true);
+        }
+        cells[i] = Some(cell);
+    }
+    cells.into_iter().flatten().collect()
+}
+
+/// `content` is the size of the largest child which spans only one track,
+/// fractions behave like `Track::Auto` when `available` is unknown
+pub fn resolve_tracks(
+    tracks: &[Track],
+    content: &[f32],
+    available: Option<f32>,
+    gap: f32,
+) -> Vec<f32> {
+    let fr: f32 = tracks
+        .iter()
+        .map(|track| match track {
+            Track::Fr(fr) => *fr,
+            _ => 0.,
+        })
+        .sum();
+    let mut sizes: Vec<f32> = tracks
+        .iter()
+        .zip(content)
+        .map(|(track, content)| match track {
+            Track::Px(px) => *px,
+            Track::Fr(_) if available.is_some() && fr > 0. => 0.,
+            Track::Fr(_) | Track::Auto => *content,
+        })
+        .collect();
+    if let (Some(available), true) = (available, fr > 0.) {
+        let gaps = gap * tracks.len().saturating_sub(1) as f32;
+        let free = (available - gaps - sizes.iter().sum::<f32>()).max(0.);
+        for (size, track) in sizes.iter_mut().zip(tracks) {
+            if let Track::Fr(f) = track {
+                *size = free * f / fr;
+            }
+        }
+    }
+    sizes
+}
+
+/// start of every track and the length of all of them with the gaps between
+pub fn offsets(sizes: &[f32], gap: f32) -> (Vec<f32>, f32) {
+    let mut offsets = Vec::with_capacity(sizes.len());
+    let mut offset = 0.;
+    for size in sizes {
+        offsets.push(offset);
+        offset += size + gap;
+    }
+    (offsets, (offset - gap).max(0.))
+}
+
+/// length of `span` tracks from `start`, including the gaps between them
+pub fn span(sizes: &[f32], start: usize, span: usize, gap: f32) -> f32 {
+    sizes[start..start + span].iter().sum::<f32>() + gap * (span - 1) as f32
+}
@@ -0,0 +1,289 @@
+use base64::Engine;
+use serde::Deserialize;
+use tiny_skia::{
+    BlendMode, ClipMask, FillRule, FilterQuality, Paint, Pixmap, PixmapPaint, Rect, SpreadMode,
+    Transform,
+};
+
+use crate::{
+    drawing::path::rounded_rect,
+    empty_pixmap, empty_rect, merge_pixmap,
+    metrics::*,
+    paint,
+    utils::{error_mapper, make_error, AppResult},
+    xywh_rect,
+};
+
+use super::{
+    container::Corner, Align, Draw, DrawResult, GraphicProps, Layout, Measure, PositionMode,
+    VerticalAlign,
+};
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "type", content = "value")]
+pub enum ImageSource {
+    /// encoded PNG/JPEG/WebP bytes
+    Bytes(Vec<u8>),
+    /// `data:image/png;base64,...`, a bare base64 string is accepted as well
+    DataUrl(String),
+}
+
+impl ImageSource {
+    pub fn decode(&self) -> AppResult<Pixmap> {
+        let image = match self {
+            ImageSource::Bytes(buff) => ::image::load_from_memory(buff),
+            ImageSource::DataUrl(url) => {
+                let data = match url.strip_prefix("data:") {
+                    Some(url) => match url.split_once(',') {
+                        Some((meta, data)) if meta.ends_with(";base64") => data,
+                        _ => return Err(make_error("only base64 data url is supported!")),
+                    },
+                    None => url,
+                };
+                let buff = base64::engine::general_purpose::STANDARD
+                    .decode(data.trim())
+                    .map_err(error_mapper)?;
+                ::image::load_from_memory(&buff)
+            }
+        }
+        .map_err(error_mapper)?
+        .to_rgba8();
+        let mut pixmap =
+            Pixmap::new(image.width(), image.height()).ok_or(make_error("image is empty!"))?;
+        for (pixel, rgba) in pixmap.pixels_mut().iter_mut().zip(image.pixels()) {
+            let [r, g, b, a] = rgba.0;
+            *pixel = tiny_skia::ColorU8::from_rgba(r, g, b, a).premultiply();
+        }
+        Ok(pixmap)
+    }
+}
+
+/// how the bitmap is resized to fit its box, same as css `object-fit`
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+pub enum ObjectFit {
+    #[default]
+    Fill,
+    Contain,
+    Cover,
+    None,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Image {
+    #[serde(flatten)]
+    pub common: GraphicProps,
+    pub source: ImageSource,
+    pub position: Option<Position<Length>>,
+    /// missing dimensions are derived from the intrinsic size of the bitmap
+    pub size: Option<Size<Length>>,
+    pub align: Option<Align>,
+    pub vertical_align: Option<VerticalAlign>,
+    pub fit: Option<ObjectFit>,
+    pub corner: Option<Corner<Length>>,
+    #[serde(skip)]
+    bitmap: Option<Pixmap>,
+    #[serde(skip)]
+    pos_bounds: Option<Rect>,
+    #[serde(skip)]
+    layout_bounds: Option<Rect>,
+    #[serde(skip)]
+    resolved_bounds: Option<Rect>,
+}
+
+impl Draw for Image {
+    fn draw(&mut self, mut pixmap: Pixmap) -> AppResult<DrawResult> {
+        let bounds = self.bounds();
+        let image_rect = self.image_rect();
+        let visible = match bounds.intersect(&image_rect) {
+            // nothing is visible when the image is left of or above the canvas
+            Some(visible)
+                if bounds.width() > 0.
+                    && bounds.height() > 0.
+                    && bounds.right() > 0.
+                    && bounds.bottom() > 0. =>
+            {
+                visible
+            }
+            _ => return Ok(DrawResult(pixmap, bounds)),
+        };
+        let bitmap = match self.bitmap {
+            Some(ref bitmap) => bitmap,
+            None => return Err(make_error("image is not laid out!")),
+        };
+        let mut paint = paint!();
+        paint.shader = tiny_skia::Pattern::new(
+            bitmap.as_ref(),
+            SpreadMode::Pad,
+            FilterQuality::Bicubic,
+            1.,
+            Transform::from_row(
+                image_rect.width() / bitmap.width() as f32,
+                0.,
+                0.,
+                image_rect.height() / bitmap.height() as f32,
+                image_rect.x(),
+                image_rect.y(),
+            ),
+        );
+        let mut image_pixmap = empty_pixmap!(bounds.right().ceil(), bounds.bottom().ceil());
+        let mut mask = ClipMask::new();
+        mask.set_path(
+            image_pixmap.width(),
+            image_pixmap.height(),
+            &self.path()?,
+            FillRule::Winding,
+            true,
+        );
+        image_pixmap.fill_rect(visible, &paint, Transform::identity(), Some(&mask));
+        Ok(DrawResult(merge_pixmap!(pixmap, image_pixmap), bounds))
+    }
+}
+
+impl Layout for Image {
+    fn layout(
+        &mut self,
+        pos_bounds: Rect,
+        layout_bounds: Rect,
+        viewport: Viewport,
+    ) -> AppResult<Rect> {
+        self.common.viewport = viewport;
+        self.measure(pos_bounds, layout_bounds, viewport)?;
+        self.resolved_bounds = Some(self.bounds());
+        Ok(self.bounds())
+    }
+}
+
+impl Measure for Image {
+    fn measure(
+        &mut self,
+        pos_bounds: Rect,
+        layout_bounds: Rect,
+        viewport: Viewport,
+    ) -> AppResult<Size> {
+        self.common.viewport = viewport;
+        self.pos_bounds = Some(pos_bounds);
+        self.layout_bounds = Some(layout_bounds);
+        if self.bitmap.is_none() {
+            self.bitmap = Some(self.source.decode()?);
+        }
+        Ok((self.width(), self.height()).into())
+    }
+}
+
+impl Image {
+    /// rect resolved by the last layout
+    pub fn resolved_bounds(&self) -> Option<Rect> {
+        self.resolved_bounds
+    }
+    /// rect the whole bitmap is mapped to, it may overflow the box when
+    /// `ObjectFit::Cover` or `ObjectFit::None` is used
+    pub fn image_rect(&self) -> Rect {
+        let (iw, ih) = self.intrinsic_size();
+        let (w, h) = (self.width(), self.height());
+        let (sx, sy) = match self.fit.unwrap_or_default() {
+            ObjectFit::Fill => (w / iw, h / ih),
+            ObjectFit::Contain => {
+                let s = (w / iw).min(h / ih);
+                (s, s)
+            }
+            ObjectFit::Cover => {
+                let s = (w / iw).max(h / ih);
+                (s, s)
+            }
+            ObjectFit::None => (1., 1.),
+        };
+        let (dw, dh) = (iw * sx, ih * sy);
+        xywh_rect!(self.x() + (w - dw) / 2., self.y() + (h - dh) / 2., dw, dh)
+    }
+
+    pub fn path(&self) -> AppResult<tiny_skia::Path> {
+        rounded_rect(
+            self.x(),
+            self.y(),
+            self.width(),
+            self.height(),
+            self.corner(),
+        )
+    }
+
+    /// percentages refer to the shorter side of the image
+    pub fn corner(&self) -> Corner {
+        let (w, h) = (self.width(), self.height());
+        self.corner
+            .map(|corner| corner.resolve(w.min(h), self.common.viewport))
+            .unwrap_or_default()
+            .get_fitted(&(w, h).into())
+    }
+
+    pub fn position(&self) -> Option<Position> {
+        self.position
+            .map(|position| position.resolve(&self.layout_bounds(), self.common.viewport))
+    }
+    pub fn size(&self) -> Option<Size> {
+        self.size
+            .map(|size| size.resolve(&self.layout_bounds(), self.common.viewport))
+    }
+
+    pub fn x(&self) -> f32 {
+        if let Some(x) = self.position().and_then(|position| position.x) {
+            return self
+                .align
+                .unwrap_or_default()
+                .x(x, self.width(), &self.layout_bounds());
+        }
+        if let Some(right) = self.position().and_then(|position| position.right) {
+            return Align::Right.x(right, self.width(), &self.layout_bounds());
+        }
+        if let Some(ref align) = self.align {
+            return align.x(0., self.width(), &self.layout_bounds());
+        }
+        self.pos_bounds().left()
+    }
+    pub fn y(&self) -> f32 {
+        if let Some(y) = self.position().and_then(|position| position.y) {
+            return self.vertical_align.unwrap_or_default().y(
+                y,
+                self.height(),
+                &self.layout_bounds(),
+            );
+        }
+        if let Some(bottom) = self.position().and_then(|position| position.bottom) {
+            return VerticalAlign::Bottom.y(bottom, self.height(), &self.layout_bounds());
+        }
+        if let Some(ref vertical_align) = self.vertical_align {
+            return vertical_align.y(0., self.height(), &self.layout_bounds());
+        }
+        self.pos_bounds().top()
+    }
+    pub fn width(&self) -> f32 {
+        let (iw, ih) = self.intrinsic_size();
+        match self.size().map(|size| (size.width, size.height)) {
+            Some((Some(w), _)) => w,
+            Some((None, Some(h))) => h * iw / ih,
+            _ => iw,
+        }
+    }
+    pub fn height(&self) -> f32 {
+        let (iw, ih) = self.intrinsic_size();
+        match self.size().map(|size| (size.width, size.height)) {
+            Some((_, Some(h))) => h,
+            Some((Some(w), None)) => w * ih / iw,
+            _ => ih,
+        }
+    }
+    fn bounds(&self) -> Rect {
+        xywh_rect!(self.x(), self.y(), self.width(), self.height())
+    }
+    fn intrinsic_size(&self) -> (f32, f32) {
+        self.bitmap
+            .as_ref()
+            .map(|bitmap| (bitmap.width() as f32, bitmap.height() as f32))
+            .unwrap_or((1., 1.))
+    }
+    fn pos_bounds(&self) -> Rect {
+        self.pos_bounds.unwrap_or(empty_rect!())
+    }
+    fn layout_bounds(&self) -> Rect {
+        self.layout_bounds.unwrap_or(empty_rect!())
+    }
+}
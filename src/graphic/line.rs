@@ -8,18 +8,20 @@ use wasm_bindgen_test::console_log;
 use crate::{
     color,
     drawing::shader::{self, create_linear_gradient},
-    effects, empty_pixmap, empty_rect, merge_pixmap,
+    effects, empty_pixmap, empty_rect, ltrb_rect, merge_pixmap,
     metrics::*,
     rgba_paint,
     utils::{self, make_error, AppResult},
 };
 
-use super::{Draw, DrawResult};
+use super::{Draw, DrawResult, GraphicProps, Layout, Measure, PositionMode};
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct Line {
-    pub from: Option<Position>,
-    pub to: Option<Position>,
+    #[serde(flatten)]
+    pub common: GraphicProps,
+    pub from: Option<Position<Length>>,
+    pub to: Option<Position<Length>>,
     pub width: f32,
     pub color: color::Rgba,
     pub shadow: Option<effects::DropShadow>,
@@ -27,11 +29,14 @@ pub struct Line {
     pub pos_bounds: Option<Rect>,
     #[serde(skip)]
     pub layout_bounds: Option<Rect>,
+    #[serde(skip)]
+    resolved_bounds: Option<Rect>,
 }
 
 impl Default for Line {
     fn default() -> Self {
         Self {
+            common: GraphicProps::default(),
             from: None,
             to: None,
             width: 1.,
@@ -39,19 +44,13 @@ impl Default for Line {
             shadow: None,
             layout_bounds: None,
             pos_bounds: None,
+            resolved_bounds: None,
         }
     }
 }
 
 impl Draw for Line {
-    fn draw(
-        &mut self,
-        mut pixmap: Pixmap,
-        pos_bounds: Rect,
-        layout_bounds: Rect,
-    ) -> AppResult<DrawResult> {
-        self.pos_bounds = Some(pos_bounds);
-        self.layout_bounds = Some(layout_bounds);
+    fn draw(&mut self, mut pixmap: Pixmap) -> AppResult<DrawResult> {
         let from = self.from();
         let to = self.to();
         let w = self.width;
@@ -81,31 +80,76 @@ impl Draw for Line {
     }
 }
 
+impl Layout for Line {
+    fn layout(
+        &mut self,
+        pos_bounds: Rect,
+        layout_bounds: Rect,
+        viewport: Viewport,
+    ) -> AppResult<Rect> {
+        self.common.viewport = viewport;
+        self.pos_bounds = Some(pos_bounds);
+        self.layout_bounds = Some(layout_bounds);
+        let from = self.from();
+        let to = self.to();
+        let bounds = ltrb_rect!(
+            from.x().min(to.x()),
+            from.y().min(to.y()),
+            from.x().max(to.x()),
+            from.y().max(to.y())
+        );
+        self.resolved_bounds = Some(bounds);
+        Ok(bounds)
+    }
+}
+
+impl Measure for Line {
+    fn measure(
+        &mut self,
+        pos_bounds: Rect,
+        layout_bounds: Rect,
+        viewport: Viewport,
+    ) -> AppResult<Size> {
+        self.common.viewport = viewport;
+        self.pos_bounds = Some(pos_bounds);
+        self.layout_bounds = Some(layout_bounds);
+        let from = self.from();
+        let to = self.to();
+        Ok(((to.x() - from.x()).abs(), (to.y() - from.y()).abs()).into())
+    }
+}
+
 impl Line {
+    /// rect resolved by the last layout
+    pub fn resolved_bounds(&self) -> Option<Rect> {
+        self.resolved_bounds
+    }
     fn from(&self) -> Position {
-        let x = self
-            .from
-            .and_then(|from| from.x)
-            .and_then(|x| Some(x + self.layout_bounds().left()))
-            .unwrap_or(self.pos_bounds().left());
-        let y = self
-            .from
-            .and_then(|from| from.y)
-            .and_then(|y| Some(y + self.layout_bounds().top()))
-            .unwrap_or(self.pos_bounds().top());
-        (x, y).into()
+        self.point(self.from)
     }
     fn to(&self) -> Position {
-        let x = self
-            .to
-            .and_then(|to| to.x)
-            .and_then(|x| Some(x + self.layout_bounds().left()))
-            .unwrap_or(self.pos_bounds().left());
-        let y = self
-            .to
-            .and_then(|to| to.y)
-            .and_then(|y| Some(y + self.layout_bounds().top()))
-            .unwrap_or(self.pos_bounds().top());
+        self.point(self.to)
+    }
+    /// resolves a point against the layout bounds, missing coordinates fall
+    /// back to the flow position
+    fn point(&self, position: Option<Position<Length>>) -> Position {
+        let bounds = self.layout_bounds();
+        let position = position.map(|position| position.resolve(&bounds, self.common.viewport));
+        let x = match position {
+            Some(Position { x: Some(x), .. }) => x + bounds.left(),
+            Some(Position {
+                right: Some(right), ..
+            }) => bounds.right() - right,
+            _ => self.pos_bounds().left(),
+        };
+        let y = match position {
+            Some(Position { y: Some(y), .. }) => y + bounds.top(),
+            Some(Position {
+                bottom: Some(bottom),
+                ..
+            }) => bounds.bottom() - bottom,
+            _ => self.pos_bounds().top(),
+        };
         (x, y).into()
     }
     fn pos_bounds(&self) -> Rect {
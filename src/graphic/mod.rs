@@ -1,9 +1,12 @@
 pub mod arc;
 pub mod container;
+pub mod flex;
+pub mod grid;
+pub mod image;
 pub mod line;
 pub mod text;
 
-use std::{f32::consts::PI, ops::Bound};
+use std::{collections::HashMap, f32::consts::PI, ops::Bound};
 
 use serde::Deserialize;
 use tiny_skia::{Paint, Path, Pixmap, Point, Rect, Shader};
@@ -18,56 +21,200 @@ use crate::{
 pub struct DrawResult(pub Pixmap, pub Rect);
 
 pub trait Draw {
-    fn draw(
+    /// paints the graphic at the rect resolved by the last `Layout::layout`
+    fn draw(&mut self, pixmap: Pixmap) -> AppResult<DrawResult>;
+}
+
+pub trait Layout {
+    /// resolves the rect of the graphic and of all its children, must be
+    /// called before `Draw::draw`
+    fn layout(
+        &mut self,
+        pos_bounds: Rect,
+        layout_bounds: Rect,
+        viewport: Viewport,
+    ) -> AppResult<Rect>;
+}
+
+pub trait Measure {
+    /// size the graphic takes when placed at `pos_bounds`, nothing is painted
+    fn measure(
         &mut self,
-        pixmap: Pixmap,
         pos_bounds: Rect,
         layout_bounds: Rect,
-    ) -> AppResult<DrawResult>;
+        viewport: Viewport,
+    ) -> AppResult<Size>;
 }
 
-#[derive(Deserialize, Debug, Clone, Copy)]
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
 pub enum Align {
+    #[default]
     Left,
     Center,
     Right,
-}
-
-impl Default for Align {
-    fn default() -> Self {
-        Align::Left
-    }
+    /// rows of text are stretched to both edges, other graphics are left aligned
+    Justify,
 }
 
 impl Align {
     fn x(&self, x: f32, w: f32, bounds: &Rect) -> f32 {
         match self {
-            Align::Left => bounds.left() + x,
+            Align::Left | Align::Justify => bounds.left() + x,
             Align::Center => bounds.left() + bounds.width() / 2. - w / 2.,
             Align::Right => bounds.right() - w - x,
         }
     }
 }
 
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+pub enum VerticalAlign {
+    #[default]
+    Top,
+    Middle,
+    Bottom,
+}
+
+impl VerticalAlign {
+    fn y(&self, y: f32, h: f32, bounds: &Rect) -> f32 {
+        match self {
+            VerticalAlign::Top => bounds.top() + y,
+            VerticalAlign::Middle => bounds.top() + bounds.height() / 2. - h / 2.,
+            VerticalAlign::Bottom => bounds.bottom() - h - y,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub enum PositionMode {
+    #[default]
+    Flow,
+    /// the graphic is laid out against the content bounds of its parent, it
+    /// neither moves its siblings nor sizes its parent
+    Absolute,
+}
+
+/// fields every graphic has, flattened into the fields of the graphic
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct GraphicProps {
+    /// key of the graphic in the result of the layout api
+    pub id: Option<String>,
+    /// `PositionMode::Absolute` takes the graphic out of the flow of its parent
+    pub position_mode: Option<PositionMode>,
+    /// paint order among the siblings, the array order is kept for equal values
+    pub z_index: Option<i32>,
+    /// space kept around the graphic when it is laid out by the flow of its parent
+    pub margin: Option<Padding<Length>>,
+    /// cell of the graphic when its parent is a grid
+    pub grid_column: Option<grid::GridLine>,
+    pub grid_row: Option<grid::GridLine>,
+    /// viewport of the last layout
+    #[serde(skip)]
+    pub viewport: Viewport,
+}
+
+impl GraphicProps {
+    pub fn position_mode(&self) -> PositionMode {
+        self.position_mode.unwrap_or_default()
+    }
+    pub fn z_index(&self) -> i32 {
+        self.z_index.unwrap_or(0)
+    }
+    /// margin resolved against `bounds`, the content bounds of the parent
+    pub fn margin(&self, bounds: &Rect, viewport: Viewport) -> Padding {
+        self.margin
+            .map(|margin| margin.resolve(bounds, viewport))
+            .unwrap_or_default()
+    }
+}
+
 #[derive(Deserialize, Debug, Clone)]
 #[serde(tag = "type", content = "value")]
 pub enum Graphic {
     Text(text::Text),
-    Container(container::Container),
+    Container(Box<container::Container>),
     Line(line::Line),
+    Arc(arc::Arc),
+    Image(image::Image),
 }
 
 impl Draw for Graphic {
-    fn draw(
+    fn draw(&mut self, pixmap: Pixmap) -> AppResult<DrawResult> {
+        match self {
+            Graphic::Container(container) => container.draw(pixmap),
+            Graphic::Line(line) => line.draw(pixmap),
+            Graphic::Text(text) => text.draw(pixmap),
+            Graphic::Arc(arc) => arc.draw(pixmap),
+            Graphic::Image(image) => image.draw(pixmap),
+        }
+    }
+}
+
+impl Layout for Graphic {
+    fn layout(
+        &mut self,
+        pos_bounds: Rect,
+        layout_bounds: Rect,
+        viewport: Viewport,
+    ) -> AppResult<Rect> {
+        match self {
+            Graphic::Container(container) => container.layout(pos_bounds, layout_bounds, viewport),
+            Graphic::Line(line) => line.layout(pos_bounds, layout_bounds, viewport),
+            Graphic::Text(text) => text.layout(pos_bounds, layout_bounds, viewport),
+            Graphic::Arc(arc) => arc.layout(pos_bounds, layout_bounds, viewport),
+            Graphic::Image(image) => image.layout(pos_bounds, layout_bounds, viewport),
+        }
+    }
+}
+
+impl Measure for Graphic {
+    fn measure(
         &mut self,
-        pixmap: Pixmap,
         pos_bounds: Rect,
         layout_bounds: Rect,
-    ) -> AppResult<DrawResult> {
+        viewport: Viewport,
+    ) -> AppResult<Size> {
         match self {
-            Graphic::Container(container) => container.draw(pixmap, pos_bounds, layout_bounds),
-            Graphic::Line(line) => line.draw(pixmap, pos_bounds, layout_bounds),
-            Graphic::Text(text) => text.draw(pixmap, pos_bounds, layout_bounds),
+            Graphic::Container(container) => container.measure(pos_bounds, layout_bounds, viewport),
+            Graphic::Line(line) => line.measure(pos_bounds, layout_bounds, viewport),
+            Graphic::Text(text) => text.measure(pos_bounds, layout_bounds, viewport),
+            Graphic::Arc(arc) => arc.measure(pos_bounds, layout_bounds, viewport),
+            Graphic::Image(image) => image.measure(pos_bounds, layout_bounds, viewport),
+        }
+    }
+}
+
+impl Graphic {
+    /// the properties every kind of graphic shares
+    pub fn props(&self) -> &GraphicProps {
+        match self {
+            Graphic::Container(container) => &container.common,
+            Graphic::Line(line) => &line.common,
+            Graphic::Text(text) => &text.common,
+            Graphic::Arc(arc) => &arc.common,
+            Graphic::Image(image) => &image.common,
+        }
+    }
+
+    pub fn resolved_bounds(&self) -> Option<Rect> {
+        match self {
+            Graphic::Container(container) => container.resolved_bounds(),
+            Graphic::Line(line) => line.resolved_bounds(),
+            Graphic::Text(text) => text.resolved_bounds(),
+            Graphic::Arc(arc) => arc.resolved_bounds(),
+            Graphic::Image(image) => image.resolved_bounds(),
+        }
+    }
+
+    /// collects the resolved rect of this graphic and of its descendants,
+    /// graphics without an id are skipped
+    pub fn collect_bounds(&self, bounds: &mut HashMap<String, Bounds>) {
+        if let (Some(id), Some(rect)) = (&self.props().id, self.resolved_bounds()) {
+            bounds.insert(id.to_string(), rect.into());
+        }
+        if let Graphic::Container(container) = self {
+            for child in container.children.iter().flatten() {
+                child.collect_bounds(bounds);
+            }
         }
     }
 }
@@ -1,204 +1,890 @@
-use rusttype::{point, Font, Scale};
+use rusttype::{point, GlyphId, Point, Scale};
+use rustybuzz::UnicodeBuffer;
 use serde::Deserialize;
-use tiny_skia::{Pixmap, PremultipliedColorU8, Rect};
+use std::ops::Range;
+use tiny_skia::{
+    BlendMode, FilterQuality, LineJoin, Paint, Path, PathBuilder, Pixmap, PixmapPaint,
+    PremultipliedColorU8, Rect, Shader, Stroke, StrokeDash, Transform,
+};
+use unicode_bidi::{get_base_direction, BidiInfo, Level};
+use unicode_linebreak::{linebreaks, BreakOpportunity};
 use wasm_bindgen_test::console_log;
 
-macro_rules! letter_width {
-    ($rect: expr) => {
-        $rect.max.x - $rect.min.x
-    };
-}
-
-macro_rules! letters_width {
-    ($letters: expr) => {
-        $letters.iter().fold(0, |a, b| a + b.max.x - b.min.x)
-    };
-}
-
 use crate::{
-    color, effects, empty_rect,
+    color,
+    drawing::shader::create_shader,
+    effects, empty_pixmap, empty_rect,
+    font::{get_typefaces, FontFamilies, Typeface},
+    merge_pixmap,
     metrics::*,
+    paint, rgba_paint,
     utils::{self, AppResult},
     xywh_rect,
 };
 
-use super::{Align, Draw, DrawResult};
+use super::{
+    container::Corner, Align, Draw, DrawResult, GraphicProps, Layout, Measure, PositionMode,
+    VerticalAlign,
+};
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct Text {
+    #[serde(flatten)]
+    pub common: GraphicProps,
+    #[serde(default)]
     pub content: String,
-    pub color: color::Rgba,
+    /// runs with their own style laid out one after another, `content` is
+    /// ignored when they are set
+    pub spans: Option<Vec<Span>>,
+    /// fill of the glyphs and their decorations, gradients are mapped across
+    /// `color_box`
+    pub color: color::Color,
+    pub color_box: Option<ColorBox>,
     pub size: f32,
-    pub font: Option<String>,
+    /// families in the order of fallback, each character is drawn with the
+    /// first of them which covers it
+    pub font: Option<FontFamilies>,
     pub line_height: Option<f32>,
-    pub position: Option<Position>,
+    /// extra space after every character
+    pub letter_spacing: Option<f32>,
+    /// extra space of every whitespace, on top of `letter_spacing`
+    pub word_spacing: Option<f32>,
+    pub position: Option<Position<Length>>,
     pub suffix: Option<String>,
     pub max_rows: Option<usize>,
+    /// `Align::Left` and `Align::Right` are the start and the end of the rows
+    /// in a right to left paragraph, which starts at the right edge when no
+    /// align is set. `Align::Justify` stretches the whitespace
+    /// of every row but the last one of a paragraph to the max width
     pub align: Option<Align>,
+    pub vertical_align: Option<VerticalAlign>,
+    /// base direction of the paragraphs, detected from the content by default
+    pub direction: Option<Direction>,
+    /// decoration of the runs which do not set their own
+    pub decoration: Option<Decoration>,
+    /// drop shadow of the glyphs, their decorations and outlines
     pub shadow: Option<effects::DropShadow>,
+    /// outline around the glyphs, color glyphs are not outlined
+    pub stroke: Option<TextStroke>,
     #[serde(skip)]
     pub pos_bounds: Option<Rect>,
     #[serde(skip)]
     pub layout_bounds: Option<Rect>,
+    #[serde(skip)]
+    resolved_bounds: Option<Rect>,
+    /// height of the rows from the last measure, the text is aligned vertically by it
+    #[serde(skip)]
+    measured_height: Option<f32>,
+}
+
+/// a run of a `Text`, unset fields are inherited from the text
+#[derive(Deserialize, Debug, Clone)]
+pub struct Span {
+    pub content: String,
+    pub color: Option<color::Color>,
+    pub size: Option<f32>,
+    /// tried before the fonts of the text
+    pub font: Option<FontFamilies>,
+    pub decoration: Option<Decoration>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct TextStroke {
+    /// distance the outline reaches out of the glyphs
+    pub width: f32,
+    pub color: color::Rgba,
+}
+
+/// the box a gradient fill of a `Text` is mapped across
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+pub enum ColorBox {
+    /// bounds of all the rows
+    #[default]
+    Block,
+    /// bounds of every row on its own
+    Row,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    Ltr,
+    Rtl,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub enum DecorationLine {
+    Underline,
+    LineThrough,
+    Overline,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub enum DecorationStyle {
+    #[default]
+    Solid,
+    Dashed,
+    Wavy,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Decoration {
+    pub line: DecorationLine,
+    pub style: Option<DecorationStyle>,
+    /// color of the glyphs by default
+    pub color: Option<color::Color>,
+    /// the underline or strikeout thickness of the font by default
+    pub thickness: Option<f32>,
+}
+
+/// a decoration resolved against the font of its run, `y` is the center of
+/// the line from the top of the row
+#[derive(Debug, Clone, PartialEq)]
+pub struct LetterDecoration {
+    pub y: f32,
+    pub thickness: f32,
+    pub color: color::Color,
+    pub style: DecorationStyle,
+}
+
+/// a span with everything inherited resolved
+struct Run<'a> {
+    content: &'a str,
+    color: color::Color,
+    size: f32,
+    /// fonts in the order of fallback, never empty
+    typefaces: Vec<&'static Typeface>,
+    decoration: Option<Decoration>,
+}
+
+impl Run<'_> {
+    /// places the decoration by the underline and strikeout metrics of the
+    /// first font of the run
+    fn decoration(&self, baseline: f32) -> Option<LetterDecoration> {
+        let decoration = self.decoration.as_ref()?;
+        let face = &self.typefaces[0].face;
+        let v_metrics = self.typefaces[0].font.v_metrics_unscaled();
+        let factor = self.size / (v_metrics.ascent - v_metrics.descent);
+        let metrics = match decoration.line {
+            DecorationLine::Underline => face.underline_metrics(),
+            DecorationLine::LineThrough => face.strikeout_metrics(),
+            DecorationLine::Overline => {
+                face.underline_metrics()
+                    .map(|metrics| ttf_parser::LineMetrics {
+                        position: face.ascender(),
+                        ..metrics
+                    })
+            }
+        };
+        // the positions of the metrics are the top of the lines
+        let (top, thickness) = match metrics {
+            Some(metrics) => (
+                baseline - metrics.position as f32 * factor,
+                metrics.thickness as f32 * factor,
+            ),
+            None => (
+                baseline
+                    + match decoration.line {
+                        DecorationLine::Underline => self.size / 10.,
+                        DecorationLine::LineThrough => -self.size * 0.3,
+                        DecorationLine::Overline => -self.size * 0.8,
+                    },
+                self.size / 16.,
+            ),
+        };
+        let thickness = decoration.thickness.unwrap_or(thickness).max(1.);
+        Some(LetterDecoration {
+            y: top + thickness / 2.,
+            thickness,
+            color: decoration.color.clone().unwrap_or(self.color.clone()),
+            style: decoration.style.unwrap_or_default(),
+        })
+    }
+    /// splits the content into pieces of the same direction and font, every
+    /// character takes the first font of the chain which covers it
+    fn pieces(&self, level: impl Fn(usize) -> Level) -> Vec<(Range<usize>, Level, usize)> {
+        let mut pieces: Vec<(Range<usize>, Level, usize)> = vec![];
+        for (i, c) in self.content.char_indices() {
+            let level = level(i);
+            let font = match pieces.last() {
+                // whitespace and punctuation stay with the font before them
+                Some(&(_, _, font))
+                    if c.is_control() || !c.is_alphanumeric() && self.typefaces[font].covers(c) =>
+                {
+                    font
+                }
+                _ => self
+                    .typefaces
+                    .iter()
+                    .position(|typeface| typeface.covers(c))
+                    .unwrap_or(0),
+            };
+            match pieces.last_mut() {
+                Some((range, l, f)) if *l == level && *f == font => range.end = i + c.len_utf8(),
+                _ => pieces.push((i..i + c.len_utf8(), level, font)),
+            }
+        }
+        pieces
+    }
+    /// shaped glyphs of the run, `offset` is the byte offset of the run in
+    /// the text and `level` gives the bidi level at a byte of the run
+    fn glyphs(
+        &self,
+        offset: usize,
+        level: impl Fn(usize) -> Level,
+        baseline: f32,
+    ) -> Vec<LetterGlyph> {
+        let mut glyphs = vec![];
+        let decoration = self.decoration(baseline);
+        for (piece, level, font) in self.pieces(level) {
+            let content = &self.content[piece.clone()];
+            let typeface = self.typefaces[font];
+            for glyph in shape(typeface, content, self.size, baseline, level.is_rtl()) {
+                let index = piece.start + glyph.index;
+                let c = match self.content[index..].chars().next() {
+                    // line feeds and other controls only affect the breaking
+                    Some(c) if !c.is_control() => c,
+                    _ => continue,
+                };
+                glyphs.push(LetterGlyph {
+                    advance: glyph.advance,
+                    pixels: glyph.pixels,
+                    color_pixels: glyph.color_pixels,
+                    paths: glyph.path.into_iter().collect(),
+                    index: offset.saturating_add(index),
+                    level,
+                    whitespace: c.is_whitespace(),
+                    color: self.color.clone(),
+                    decoration: decoration.clone(),
+                });
+            }
+        }
+        glyphs
+    }
 }
 
 impl Draw for Text {
-    fn draw(
+    fn draw(&mut self, mut pixmap: Pixmap) -> AppResult<DrawResult> {
+        if self.is_empty() {
+            return Ok(DrawResult(pixmap, self.pos_bounds()));
+        }
+        let allocated = self.allocated();
+        let bounds = self.block_bounds(&allocated);
+        // the layer covers the text when the canvas grows with its content,
+        // the outline reaches out of the rows and the shadow grows the layer
+        let outset = self.stroke.map(|stroke| stroke.width).unwrap_or(0.);
+        let w = (pixmap.width() as f32).max((bounds.right() + outset).ceil()) as i32;
+        let h = (pixmap.height() as f32).max((bounds.bottom() + outset).ceil()) as i32;
+        let tops: Vec<i32> = (0..allocated.len()).map(|row| self.row_top(row)).collect();
+        let decorations = self.decorations(&allocated, &tops);
+        let color_boxes = self.color_boxes(&allocated);
+        // glyphs are drawn into their own layer, the shadow is cast by all of it
+        let mut layer = empty_pixmap!(w, h);
+        if let Some(stroke) = self.stroke {
+            let paint = rgba_paint!(stroke.color);
+            let outline = Stroke {
+                width: stroke.width * 2.,
+                line_join: LineJoin::Round,
+                ..Stroke::default()
+            };
+            for path in self.strokes(&allocated, &tops) {
+                layer.stroke_path(&path, &paint, &outline, Transform::identity(), None);
+            }
+        }
+        // coverage of the glyphs and decorations of every fill, the fill is
+        // painted through it
+        // each mask covers its color box and the ink reaching out of it
+        let ink = self.size + outset;
+        let mut fills: Vec<Fill> = vec![];
+        for ((letters, &color_box), &top) in allocated.iter().zip(&color_boxes).zip(&tops) {
+            let mut offset_x = self.row_start(row_width(letters));
+            for letter in letters {
+                let x = offset_x.round() as i32;
+                let target = fill_mask(&mut fills, &letter.color, color_box, ink, w, h);
+                for &LetterPixel(px, py, v) in &letter.pixels {
+                    let coverage = color::Rgba(255, 255, 255, (255. * v).round() as u8);
+                    let (px, py) = (x + px - target.left, top + py - target.top);
+                    blend_pixel(&mut target.mask, px, py, coverage);
+                }
+                let fill = GlyphFill::new(&letter.color, color_box, x, top, &letter.color_pixels)?;
+                for &ColorPixel(px, py, color) in &letter.color_pixels {
+                    let (px, py) = (x + px, top + py);
+                    let color = match (color, &fill) {
+                        (GlyphColor::Rgba(color), _) => color,
+                        (GlyphColor::Text(v), Some(fill)) => fill.at(px, py, v),
+                        (GlyphColor::Text(_), None) => continue,
+                    };
+                    blend_pixel(&mut layer, px, py, color);
+                }
+                offset_x += letter.advance;
+            }
+        }
+        for (row, path, stroke, color) in decorations {
+            let target = fill_mask(&mut fills, &color, color_boxes[row], ink, w, h);
+            let transform = Transform::from_translate(-target.left as f32, -target.top as f32);
+            target.mask.stroke_path(
+                &path,
+                &rgba_paint!(color::Rgba(255, 255, 255, 255)),
+                &stroke,
+                transform,
+                None,
+            );
+        }
+        for Fill {
+            color,
+            rect,
+            left,
+            top,
+            mut mask,
+        } in fills
+        {
+            let mut paint = paint!();
+            paint.shader = create_shader(
+                &color,
+                (rect.x() - left as f32, rect.y() - top as f32).into(),
+                (rect.width(), rect.height()).into(),
+                Corner::default(),
+            )?;
+            // keeps the fill where the coverage is
+            paint.blend_mode = BlendMode::SourceIn;
+            let (mask_w, mask_h) = (mask.width() as f32, mask.height() as f32);
+            mask.fill_rect(
+                xywh_rect!(0., 0., mask_w, mask_h),
+                &paint,
+                Transform::identity(),
+                None,
+            );
+            layer = merge_pixmap!(layer, mask, left, top, BlendMode::SourceOver);
+        }
+        if let Some(shadow) = self.shadow {
+            layer = shadow.draw(layer)?;
+        }
+        let pixmap = merge_pixmap!(pixmap, layer);
+        Ok(DrawResult(pixmap, bounds))
+    }
+}
+
+/// composites the color over the pixel with source-over, overlapping glyphs
+/// and their anti-aliased edges blend with what is under them
+fn blend_pixel(pixmap: &mut Pixmap, x: i32, y: i32, color::Rgba(r, g, b, a): color::Rgba) {
+    let (w, h) = (pixmap.width() as i32, pixmap.height() as i32);
+    if x < 0 || y < 0 || x >= w || y >= h || a == 0 {
+        return;
+    }
+    let pixel = &mut pixmap.pixels_mut()[(y * w + x) as usize];
+    let src = tiny_skia::ColorU8::from_rgba(r, g, b, a).premultiply();
+    let inverse = 255 - src.alpha() as u32;
+    let over = |s: u8, d: u8| s + ((d as u32 * inverse + 127) / 255) as u8;
+    *pixel = PremultipliedColorU8::from_rgba(
+        over(src.red(), pixel.red()),
+        over(src.green(), pixel.green()),
+        over(src.blue(), pixel.blue()),
+        over(src.alpha(), pixel.alpha()),
+    )
+    .unwrap_or(src);
+}
+
+/// the fill of the text under the layers of a color glyph in the color of
+/// the text, placed at `left` and `top` of the canvas
+struct GlyphFill {
+    left: i32,
+    top: i32,
+    pixmap: Pixmap,
+}
+
+impl GlyphFill {
+    /// paints `color` mapped across `rect` under the pixels of the glyph at
+    /// (x, y) which take the color of the text, if it has any
+    fn new(
+        color: &color::Color,
+        rect: Rect,
+        x: i32,
+        y: i32,
+        pixels: &[ColorPixel],
+    ) -> AppResult<Option<Self>> {
+        let bounds = pixels
+            .iter()
+            .filter(|pixel| matches!(pixel.2, GlyphColor::Text(_)))
+            .map(|&ColorPixel(px, py, _)| (x + px, y + py))
+            .fold(None, |bounds, (px, py)| match bounds {
+                None => Some((px, py, px, py)),
+                Some((l, t, r, b)) => Some((l.min(px), t.min(py), r.max(px), b.max(py))),
+            });
+        let (l, t, r, b) = match bounds {
+            Some(bounds) => bounds,
+            None => return Ok(None),
+        };
+        let (w, h) = ((r - l + 1) as f32, (b - t + 1) as f32);
+        let mut pixmap = empty_pixmap!(w, h);
+        let mut paint = paint!();
+        paint.shader = create_shader(
+            color,
+            (rect.x() - l as f32, rect.y() - t as f32).into(),
+            (rect.width(), rect.height()).into(),
+            Corner::default(),
+        )?;
+        pixmap.fill_rect(
+            xywh_rect!(0., 0., w, h),
+            &paint,
+            Transform::identity(),
+            None,
+        );
+        Ok(Some(Self {
+            left: l,
+            top: t,
+            pixmap,
+        }))
+    }
+    /// the fill at (x, y) of the canvas with the coverage `v`
+    fn at(&self, x: i32, y: i32, v: f32) -> color::Rgba {
+        let pixel = self
+            .pixmap
+            .pixel((x - self.left) as u32, (y - self.top) as u32);
+        match pixel.map(|pixel| pixel.demultiply()) {
+            Some(pixel) => {
+                let alpha = (pixel.alpha() as f32 * v).round() as u8;
+                color::Rgba(pixel.red(), pixel.green(), pixel.blue(), alpha)
+            }
+            None => color::Rgba(0, 0, 0, 0),
+        }
+    }
+}
+
+/// a fill of glyphs, mapped across the rect and masked by the coverage
+/// in the mask, which starts at the left and top of the layer
+struct Fill {
+    color: color::Color,
+    rect: Rect,
+    left: i32,
+    top: i32,
+    mask: Pixmap,
+}
+
+impl Fill {
+    /// the mask is the rect grown by the ink, inside the w by h layer
+    fn new(color: &color::Color, rect: Rect, ink: f32, w: i32, h: i32) -> Self {
+        let left = ((rect.x() - ink).floor() as i32).clamp(0, w - 1);
+        let top = ((rect.y() - ink).floor() as i32).clamp(0, h - 1);
+        let right = ((rect.right() + ink).ceil() as i32).clamp(left + 1, w);
+        let bottom = ((rect.bottom() + ink).ceil() as i32).clamp(top + 1, h);
+        Fill {
+            color: color.clone(),
+            rect,
+            left,
+            top,
+            mask: empty_pixmap!(right - left, bottom - top),
+        }
+    }
+}
+
+/// coverage of the fill, added when the text has no such fill yet
+fn fill_mask<'a>(
+    fills: &'a mut Vec<Fill>,
+    color: &color::Color,
+    rect: Rect,
+    ink: f32,
+    w: i32,
+    h: i32,
+) -> &'a mut Fill {
+    let i = match fills
+        .iter()
+        .position(|fill| fill.color == *color && fill.rect == rect)
+    {
+        Some(i) => i,
+        None => {
+            fills.push(Fill::new(color, rect, ink, w, h));
+            fills.len() - 1
+        }
+    };
+    &mut fills[i]
+}
+
+impl Layout for Text {
+    fn layout(
         &mut self,
-        mut pixmap: Pixmap,
         pos_bounds: Rect,
         layout_bounds: Rect,
-    ) -> AppResult<DrawResult> {
+        viewport: Viewport,
+    ) -> AppResult<Rect> {
+        self.common.viewport = viewport;
+        let size = self.measure(pos_bounds, layout_bounds, viewport)?;
+        let bounds = match self.is_empty() {
+            true => pos_bounds,
+            false => xywh_rect!(
+                self.row_start(size.width()),
+                self.y(),
+                size.width(),
+                size.height()
+            ),
+        };
+        self.resolved_bounds = Some(bounds);
+        Ok(bounds)
+    }
+}
+
+impl Measure for Text {
+    fn measure(
+        &mut self,
+        pos_bounds: Rect,
+        layout_bounds: Rect,
+        viewport: Viewport,
+    ) -> AppResult<Size> {
+        self.common.viewport = viewport;
         self.pos_bounds = Some(pos_bounds);
         self.layout_bounds = Some(layout_bounds);
-        if self.content.chars().count() <= 0 {
-            return Ok(DrawResult(pixmap, pos_bounds));
+        self.measured_height = None;
+        if self.is_empty() {
+            return Ok(Size::default());
         }
-        let w = pixmap.width() as i32;
-        let h = pixmap.height() as i32;
-        let data = pixmap.pixels_mut();
         let allocated = self.allocated();
-        let mut offset_y = self.y() as i32;
-        let color::Rgba(r, g, b, a) = self.color;
-        let mut min_x = self.row_start(0.) as i32;
-        let min_y = offset_y;
-        let mut max_w = 0;
-        let max_h = allocated.len() as f32 * self.line_height() as f32;
-        for letters in allocated {
-            let row_w = letters_width!(letters
+        let w = allocated
+            .iter()
+            .map(|letters| row_width(letters))
+            .fold(0., f32::max);
+        let h = allocated.len() as f32 * self.line_height();
+        self.measured_height = Some(h);
+        Ok((w, h).into())
+    }
+}
+
+impl Text {
+    /// rect resolved by the last layout
+    pub fn resolved_bounds(&self) -> Option<Rect> {
+        self.resolved_bounds
+    }
+    /// defaults to the largest font size of the runs
+    pub fn line_height(&self) -> f32 {
+        self.line_height.unwrap_or_else(|| {
+            self.runs()
                 .iter()
-                .map(|letter| &letter.bounds)
-                .collect::<Vec<_>>());
-            max_w = max_w.max(row_w);
-            let mut offset_x = self.row_start(row_w as f32) as i32;
-            min_x = min_x.min(offset_x);
+                .map(|run| run.size)
+                .fold(self.size, f32::max)
+        })
+    }
+    /// content of all the runs
+    pub fn text(&self) -> String {
+        self.runs().iter().map(|run| run.content).collect()
+    }
+    fn is_empty(&self) -> bool {
+        self.runs().iter().all(|run| run.content.is_empty())
+    }
+    fn runs(&self) -> Vec<Run<'_>> {
+        let spans = match self.spans {
+            Some(ref spans) => spans,
+            None => {
+                return vec![Run {
+                    content: &self.content,
+                    color: self.color.clone(),
+                    size: self.size,
+                    typefaces: self.typefaces(),
+                    decoration: self.decoration.clone(),
+                }]
+                .into_iter()
+                .filter(|run| !run.typefaces.is_empty())
+                .collect()
+            }
+        };
+        spans
+            .iter()
+            .map(|span| {
+                // the fonts of the span fall back to the fonts of the text
+                let families: Vec<String> = span
+                    .font
+                    .iter()
+                    .flat_map(|font| font.0.iter().cloned())
+                    .chain(self.families())
+                    .collect();
+                Run {
+                    content: &span.content,
+                    color: span.color.clone().unwrap_or(self.color.clone()),
+                    size: span.size.unwrap_or(self.size),
+                    typefaces: get_typefaces(&families),
+                    decoration: span.decoration.clone().or(self.decoration.clone()),
+                }
+            })
+            .filter(|run| !run.typefaces.is_empty())
+            .collect()
+    }
+    /// distance from the top of a row to the baseline shared by all the runs
+    fn baseline(&self) -> f32 {
+        let line_height = self.line_height();
+        self.runs()
+            .iter()
+            .map(|run| {
+                let v_metrics = run.typefaces[0].font.v_metrics(Scale::uniform(run.size));
+                let h = (v_metrics.ascent - v_metrics.descent).ceil();
+                v_metrics.ascent + (line_height - h) / 2.
+            })
+            .fold(0., f32::max)
+    }
+    /// lines of the decorations of every row with the index of the row, a
+    /// line spans the neighbouring glyphs with the same decoration and breaks
+    /// with the rows
+    fn decorations(
+        &self,
+        allocated: &[Vec<LetterGlyph>],
+        tops: &[i32],
+    ) -> Vec<(usize, Path, Stroke, color::Color)> {
+        let mut lines = vec![];
+        for (row, (letters, &top)) in allocated.iter().zip(tops).enumerate() {
+            let top = top as f32;
+            let mut x = self.row_start(row_width(letters));
+            let mut segment: Option<(f32, f32, LetterDecoration)> = None;
+            let mut end = |segment: Option<(f32, f32, LetterDecoration)>| {
+                if let Some((path, stroke, color)) =
+                    segment.and_then(|segment| decoration_line(segment, top))
+                {
+                    lines.push((row, path, stroke, color));
+                }
+            };
             for letter in letters {
-                let LetterGlyph { bounds, pixels } = letter;
-                for LetterPixel(x, y, v) in pixels {
-                    let x = offset_x + x as i32;
-                    let y = offset_y;
-                    if x < 0 || y < 0 {
-                        continue;
+                segment = match (segment, &letter.decoration) {
+                    (Some((start, _, current)), Some(decoration)) if current == *decoration => {
+                        Some((start, x + letter.advance, current))
                     }
-                    let offset = ((y - 1) * w + x) as usize;
-
-                    if data.get(offset).is_some() {
-                        data[offset] = tiny_skia::ColorU8::from_rgba(r, g, b, (a as f32 * v) as u8)
-                            .premultiply();
+                    (segment, decoration) => {
+                        end(segment);
+                        decoration
+                            .clone()
+                            .map(|decoration| (x, x + letter.advance, decoration))
                     }
-                    offset_x += letter_width!(bounds);
-                }
+                };
+                x += letter.advance;
             }
-            offset_y += self.line_height() as i32;
+            end(segment);
         }
-        Ok(DrawResult(
-            pixmap,
-            xywh_rect!(min_x as f32, min_y as f32, max_w as f32, max_h as f32),
-        ))
+        lines
     }
-}
-
-impl Text {
-    pub fn line_height(&self) -> f32 {
-        self.line_height.unwrap_or(self.size)
+    /// rect a gradient fill is mapped across for every row
+    fn color_boxes(&self, allocated: &[Vec<LetterGlyph>]) -> Vec<Rect> {
+        let line_height = self.line_height();
+        let widths: Vec<f32> = allocated.iter().map(|letters| row_width(letters)).collect();
+        match self.color_box.unwrap_or_default() {
+            ColorBox::Block => {
+                let block = self.block_bounds(allocated);
+                let block = xywh_rect!(
+                    block.x(),
+                    block.y(),
+                    block.width().max(1.),
+                    block.height().max(1.)
+                );
+                vec![block; allocated.len()]
+            }
+            ColorBox::Row => widths
+                .iter()
+                .enumerate()
+                .map(|(row, &row_w)| {
+                    xywh_rect!(
+                        self.row_start(row_w),
+                        self.y() + row as f32 * line_height,
+                        row_w.max(1.),
+                        line_height.max(1.)
+                    )
+                })
+                .collect(),
+        }
     }
-    pub fn font(&self) -> Option<&'static Font<'static>> {
-        let font: String = if let Some(ref f) = self.font {
-            f.clone()
-        } else {
-            "".into()
-        };
-        crate::font::get_font(font)
+    /// bounds of all the rows
+    fn block_bounds(&self, allocated: &[Vec<LetterGlyph>]) -> Rect {
+        let widths: Vec<f32> = allocated.iter().map(|letters| row_width(letters)).collect();
+        let w = widths.iter().copied().fold(0., f32::max);
+        let x = widths
+            .iter()
+            .map(|&row_w| self.row_start(row_w))
+            .fold(self.row_start(w), f32::min);
+        xywh_rect!(x, self.y(), w, allocated.len() as f32 * self.line_height())
+    }
+    /// top of the row in whole pixels, the glyphs, their outlines and
+    /// decorations of the row are all placed from it
+    fn row_top(&self, row: usize) -> i32 {
+        (self.y() + row as f32 * self.line_height()).round() as i32
+    }
+    /// outlines of the glyphs of every row, placed like their pixels
+    fn strokes(&self, allocated: &[Vec<LetterGlyph>], tops: &[i32]) -> Vec<Path> {
+        let mut paths = vec![];
+        for (letters, &top) in allocated.iter().zip(tops) {
+            let mut x = self.row_start(row_width(letters));
+            for letter in letters {
+                let transform = Transform::from_translate(x.round(), top as f32);
+                paths.extend(
+                    letter
+                        .paths
+                        .iter()
+                        .filter_map(|path| path.clone().transform(transform)),
+                );
+                x += letter.advance;
+            }
+        }
+        paths
+    }
+    /// families of `font`, the unnamed font of the `font_set` when it is not set
+    fn families(&self) -> Vec<String> {
+        match self.font {
+            Some(ref font) if !font.0.is_empty() => font.0.clone(),
+            _ => vec![String::new()],
+        }
+    }
+    pub fn typefaces(&self) -> Vec<&'static Typeface> {
+        get_typefaces(&self.families())
     }
 
+    /// breaks the glyphs into rows at the opportunities of UAX #14, a word
+    /// which is wider than a row is broken between its glyphs. the rows are
+    /// in visual order
     pub fn allocated(&self) -> Vec<Vec<LetterGlyph>> {
         let (c_metrics, e_metrics) = self.metrics();
-        let mut allocated: Vec<Vec<LetterGlyph>> = vec![];
+        let text = self.text();
+        let mut breaks = linebreaks(&text).peekable();
+        let mut allocated: Vec<Vec<LetterGlyph>> = vec![vec![]];
         let max_rows = self.max_rows();
-        let max_width = self.max_width() as i32;
-        let mut w = 0_i32;
-        for letter in c_metrics {
-            if allocated.last().is_none() {
-                allocated.push(vec![]);
-            }
-            let rows = allocated.len();
-            let row = allocated.last_mut().unwrap();
-            let letter_w = letter.bounds.max.x - letter.bounds.min.x;
-            w += letter_w;
-            if w <= max_width {
-                row.push(letter);
-                continue;
+        let max_width = self.max_width();
+        let mut w = 0_f32;
+        // index in the last row before which it may be broken
+        let mut break_at = None;
+        // whether a row is the last one of its paragraph
+        let mut paragraph_ends = vec![];
+        'letters: for letter in c_metrics {
+            // every mandatory break starts a row, consecutive line feeds leave
+            // empty rows between them
+            let mut mandatory = 0;
+            while let Some(&(offset, opportunity)) = breaks.peek() {
+                if offset > letter.index {
+                    break;
+                }
+                breaks.next();
+                match opportunity {
+                    BreakOpportunity::Mandatory => mandatory += 1,
+                    BreakOpportunity::Allowed => break_at = allocated.last().map(|row| row.len()),
+                }
             }
-            if rows >= max_rows {
-                let suffix_w = e_metrics
-                    .as_ref()
-                    .and_then(|m| Some(letter_width!(&m.bounds)))
-                    .unwrap_or(0_i32);
-                while w + suffix_w > max_width {
-                    if row.len() <= 1 {
-                        break;
+            // whitespace hangs at the end of the row instead of wrapping, and
+            // the glyphs of a cluster are never split
+            let overflow = !letter.whitespace
+                && allocated
+                    .last()
+                    .and_then(|row| row.last())
+                    .is_some_and(|last| last.index != letter.index)
+                && w + letter.advance > max_width;
+            for _ in 0..mandatory.max(overflow as usize) {
+                let rows = allocated.len();
+                let row = allocated.last_mut().unwrap();
+                if rows >= max_rows {
+                    let suffix_w = e_metrics.as_ref().map(|m| m.advance).unwrap_or(0.);
+                    while w + suffix_w > max_width {
+                        if row.len() <= 1 {
+                            break;
+                        }
+                        w -= row.pop().unwrap().advance;
                     }
-                    w -= letter_width!(row.pop().unwrap().bounds);
-                }
-                if let Some(e_metrics) = e_metrics {
-                    row.push(e_metrics);
+                    if let Some(e_metrics) = e_metrics {
+                        row.push(e_metrics);
+                    }
+                    break 'letters;
                 }
-                break;
+                let next = match break_at {
+                    Some(at) if mandatory == 0 && at > 0 => row.split_off(at),
+                    _ => vec![],
+                };
+                w = next.iter().map(|letter| letter.advance).sum();
+                allocated.push(next);
+                paragraph_ends.push(mandatory > 0);
+                break_at = None;
             }
-            w = letter_width!(letter.bounds);
-            allocated.push(vec![letter]);
+            w += letter.advance;
+            allocated.last_mut().unwrap().push(letter);
         }
+        paragraph_ends.push(true);
+        let justify = matches!(self.align(), Align::Justify);
         allocated
+            .into_iter()
+            .zip(paragraph_ends)
+            .map(|(letters, end)| {
+                let mut letters = reorder(letters);
+                if justify && !end {
+                    justify_row(&mut letters, max_width);
+                }
+                letters
+            })
+            .collect()
     }
 
-    pub fn metrics<'a>(&self) -> (Vec<LetterGlyph>, Option<LetterGlyph>) {
-        let font = self.font().unwrap();
-        let scale = Scale::uniform(self.size);
-        let v_metrics = font.v_metrics(scale);
-        let h = (v_metrics.descent - v_metrics.ascent).ceil();
-        let y = v_metrics.ascent + (self.line_height() - h) / 2.;
-        let start = point(0., y);
-        let glyphs: Vec<_> = font
-            .layout(&self.content, scale, start)
-            .filter(|glyph| glyph.pixel_bounding_box().is_some())
-            .map(|glyph| {
-                let bounds = glyph.pixel_bounding_box().unwrap();
-                let w = bounds.width() as usize;
-                let h = bounds.height() as usize;
-                let mut pixels = Vec::with_capacity(w * h / 2);
-                glyph.draw(|x, y, v| pixels.push(LetterPixel(x, y, v)));
-                LetterGlyph { bounds, pixels }
-            })
-            .collect();
-        let mut suffix = None;
-        if self.suffix.is_some() {
-            let suffix_glyphs: Vec<_> = font.layout(self.suffix(), scale, start).collect();
-            let bounds = rusttype::Rect {
-                min: suffix_glyphs
-                    .first()
-                    .unwrap()
-                    .pixel_bounding_box()
-                    .unwrap()
-                    .min,
-                max: suffix_glyphs
-                    .last()
-                    .unwrap()
-                    .pixel_bounding_box()
-                    .unwrap()
-                    .max,
-            };
-            let mut pixels = vec![];
-            for (i, glyph) in suffix_glyphs.iter().enumerate() {
-                let left = suffix_glyphs
-                    .get(i - 1)
-                    .and_then(|m| m.pixel_bounding_box())
-                    .and_then(|b| Some(b.max.x))
-                    .unwrap_or(0) as u32;
-                let offset = glyph.pixel_bounding_box().unwrap().min.x as u32;
-                glyph.draw(|x, y, a| pixels.push(LetterPixel(x + offset - left, y, a)))
+    /// shaped glyphs of every run, and the glyph of the suffix
+    pub fn metrics(&self) -> (Vec<LetterGlyph>, Option<LetterGlyph>) {
+        let baseline = self.baseline();
+        let text = self.text();
+        let bidi = BidiInfo::new(&text, self.direction.map(|_| self.level()));
+        let levels = &bidi.levels;
+        let mut offset = 0;
+        let mut glyphs = vec![];
+        for run in self.runs() {
+            let run_offset = offset;
+            glyphs.extend(run.glyphs(run_offset, |i| levels[run_offset + i], baseline));
+            offset += run.content.len();
+        }
+        // the spacing follows the last glyph of every cluster
+        let (letter_spacing, word_spacing) = (
+            self.letter_spacing.unwrap_or(0.),
+            self.word_spacing.unwrap_or(0.),
+        );
+        for i in 0..glyphs.len() {
+            if glyphs.get(i + 1).map(|next| next.index) != Some(glyphs[i].index) {
+                glyphs[i].advance += letter_spacing;
+                if glyphs[i].whitespace {
+                    glyphs[i].advance += word_spacing;
+                }
             }
-            suffix = Some(LetterGlyph { bounds, pixels })
         }
+        let typefaces = self.typefaces();
+        let suffix = match self.suffix {
+            Some(_) if !typefaces.is_empty() => {
+                let run = Run {
+                    content: self.suffix(),
+                    color: self.color.clone(),
+                    size: self.size,
+                    typefaces,
+                    decoration: None,
+                };
+                let level = self.level();
+                let mut pixels = vec![];
+                let mut color_pixels = vec![];
+                let mut paths = vec![];
+                let mut advance = 0_f32;
+                for glyph in run.glyphs(usize::MAX, |_| level, baseline) {
+                    let x = advance.round() as i32;
+                    pixels.extend(
+                        glyph
+                            .pixels
+                            .into_iter()
+                            .map(|LetterPixel(px, py, v)| LetterPixel(px + x, py, v)),
+                    );
+                    color_pixels.extend(
+                        glyph
+                            .color_pixels
+                            .into_iter()
+                            .map(|ColorPixel(px, py, color)| ColorPixel(px + x, py, color)),
+                    );
+                    let transform = Transform::from_translate(x as f32, 0.);
+                    paths.extend(
+                        glyph
+                            .paths
+                            .into_iter()
+                            .filter_map(|path| path.transform(transform)),
+                    );
+                    advance += glyph.advance;
+                }
+                Some(LetterGlyph {
+                    advance,
+                    pixels,
+                    color_pixels,
+                    paths,
+                    index: usize::MAX,
+                    level,
+                    whitespace: false,
+                    color: self.color.clone(),
+                    decoration: None,
+                })
+            }
+            _ => None,
+        };
         (glyphs, suffix)
     }
 
@@ -207,36 +893,77 @@ impl Text {
     }
     // x is just an anchor position, not start position of first letter
     pub fn x(&self) -> f32 {
-        if let Some(ref position) = self.position {
+        if let Some(ref position) = self.position() {
             if let Some(ref x) = position.x {
-                return self
-                    .align
-                    .or(Some(Align::Left))
-                    .unwrap()
-                    .x(*x, 0., &self.layout_bounds());
+                return self.align().x(*x, 0., &self.layout_bounds());
+            }
+            if let Some(ref right) = position.right {
+                return Align::Right.x(*right, 0., &self.layout_bounds());
             }
         }
-        if let Some(ref align) = self.align {
-            return align.x(0., 0., &self.layout_bounds());
+        if self.align.is_some() || self.level().is_rtl() {
+            return self.align().x(0., 0., &self.layout_bounds());
         }
         self.pos_bounds().left()
     }
+    /// rows anchored to the right edge are right aligned unless `x` is set
+    pub fn align(&self) -> Align {
+        match (self.position(), self.align) {
+            (
+                Some(Position {
+                    x: None,
+                    right: Some(_),
+                    ..
+                }),
+                _,
+            ) => Align::Right,
+            (_, None | Some(Align::Left)) if self.level().is_rtl() => Align::Right,
+            (_, Some(Align::Right)) if self.level().is_rtl() => Align::Left,
+            (_, align) => align.unwrap_or_default(),
+        }
+    }
+    /// paragraph level of the text, the first strong character decides
+    /// it when `direction` is not set
+    pub fn level(&self) -> Level {
+        match self.direction {
+            Some(Direction::Ltr) => Level::ltr(),
+            Some(Direction::Rtl) => Level::rtl(),
+            None => match get_base_direction(self.text().as_str()) {
+                unicode_bidi::Direction::Rtl => Level::rtl(),
+                _ => Level::ltr(),
+            },
+        }
+    }
     pub fn row_start(&self, w: f32) -> f32 {
         let x = self.x();
-        match self.align.unwrap_or_default() {
-            Align::Left => x,
+        match self.align() {
+            Align::Left | Align::Justify => x,
             Align::Center => x - w / 2.,
             Align::Right => x - w,
         }
     }
     pub fn y(&self) -> f32 {
-        if let Some(ref position) = self.position {
+        let h = self.measured_height.unwrap_or(0.);
+        if let Some(ref position) = self.position() {
             if let Some(ref y) = position.y {
-                return *y + self.layout_bounds().top();
+                return self
+                    .vertical_align
+                    .unwrap_or_default()
+                    .y(*y, h, &self.layout_bounds());
             }
+            if let Some(ref bottom) = position.bottom {
+                return VerticalAlign::Bottom.y(*bottom, h, &self.layout_bounds());
+            }
+        }
+        if let Some(ref vertical_align) = self.vertical_align {
+            return vertical_align.y(0., h, &self.layout_bounds());
         }
         self.pos_bounds().top()
     }
+    pub fn position(&self) -> Option<Position> {
+        self.position
+            .map(|position| position.resolve(&self.layout_bounds(), self.common.viewport))
+    }
     fn suffix(&self) -> &str {
         if let Some(ref s) = self.suffix {
             s
@@ -251,7 +978,13 @@ impl Text {
         self.pos_bounds.unwrap_or(empty_rect!())
     }
     pub fn max_width(&self) -> f32 {
-        self.layout_bounds().width() - self.x()
+        let bounds = self.layout_bounds();
+        let x = self.x();
+        match self.align() {
+            Align::Left | Align::Justify => bounds.right() - x,
+            Align::Center => bounds.width(),
+            Align::Right => x - bounds.left(),
+        }
     }
     pub fn max_height(&self) -> f32 {
         self.max_rows() as f32 * self.line_height()
@@ -259,8 +992,256 @@ impl Text {
 }
 
 pub struct LetterGlyph {
-    pub bounds: rusttype::Rect<i32>,
+    /// distance to the pen position of the next glyph, kerning included
+    pub advance: f32,
+    /// coverage relative to the pen position at the top of the row
     pub pixels: Vec<LetterPixel>,
+    /// pixels of a color glyph, in the same space as `pixels`
+    pub color_pixels: Vec<ColorPixel>,
+    /// outlines of the glyph, in the same space as `pixels`
+    pub paths: Vec<Path>,
+    /// byte offset of the character in `Text::text`
+    pub index: usize,
+    /// bidi embedding level, the rows are reordered by it
+    pub level: Level,
+    pub whitespace: bool,
+    pub color: color::Color,
+    pub decoration: Option<LetterDecoration>,
+}
+
+pub struct LetterPixel(pub i32, pub i32, pub f32);
+
+/// a pixel of a color glyph, drawn over the pixels of the layers below it
+pub struct ColorPixel(pub i32, pub i32, pub GlyphColor);
+
+#[derive(Clone, Copy)]
+pub enum GlyphColor {
+    /// a color of the palette or the bitmap of the font
+    Rgba(color::Rgba),
+    /// coverage of a layer in the color of the text
+    Text(f32),
+}
+
+/// path of a decoration from `start` to `end` of a row at `top`, stroked in
+/// its style
+fn decoration_line(
+    (start, end, decoration): (f32, f32, LetterDecoration),
+    top: f32,
+) -> Option<(Path, Stroke, color::Color)> {
+    let LetterDecoration {
+        y,
+        thickness,
+        color,
+        style,
+    } = decoration;
+    let y = top + y;
+    let mut pb = PathBuilder::new();
+    pb.move_to(start, y);
+    let mut stroke = Stroke {
+        width: thickness,
+        ..Stroke::default()
+    };
+    match style {
+        DecorationStyle::Solid => pb.line_to(end, y),
+        DecorationStyle::Dashed => {
+            pb.line_to(end, y);
+            stroke.dash = StrokeDash::new(vec![thickness * 3., thickness * 2.], 0.);
+        }
+        DecorationStyle::Wavy => {
+            // half waves alternating above and below the center of the line
+            let half = thickness * 2.;
+            let mut x = start;
+            let mut amplitude = thickness * 1.5;
+            while x < end {
+                let next = (x + half).min(end);
+                pb.quad_to((x + next) / 2., y - amplitude, next, y);
+                amplitude = -amplitude;
+                x = next;
+            }
+        }
+    }
+    Some((pb.finish()?, stroke, color))
+}
+
+/// a glyph out of the shaper, `index` is the byte offset of its cluster
+struct ShapedGlyph {
+    index: usize,
+    advance: f32,
+    pixels: Vec<LetterPixel>,
+    color_pixels: Vec<ColorPixel>,
+    path: Option<Path>,
 }
 
-pub struct LetterPixel(pub u32, pub u32, pub f32);
+/// collects the outline of a glyph into a path
+struct OutlinePath(PathBuilder);
+
+impl rusttype::OutlineBuilder for OutlinePath {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.0.move_to(x, y);
+    }
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.0.line_to(x, y);
+    }
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        self.0.quad_to(x1, y1, x, y);
+    }
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        self.0.cubic_to(x1, y1, x2, y2, x, y);
+    }
+    fn close(&mut self) {
+        self.0.close();
+    }
+}
+
+/// outline of the glyph with its origin at `origin`
+fn outline_path(typeface: &Typeface, glyph: u16, size: f32, origin: Point<f32>) -> Option<Path> {
+    let glyph = typeface
+        .font
+        .glyph(GlyphId(glyph))
+        .scaled(Scale::uniform(size))
+        .positioned(origin);
+    let bounds = glyph.pixel_bounding_box()?;
+    let mut builder = OutlinePath(PathBuilder::new());
+    if !glyph.build_outline(&mut builder) {
+        return None;
+    }
+    builder.0.finish()?.transform(Transform::from_translate(
+        bounds.min.x as f32,
+        bounds.min.y as f32,
+    ))
+}
+
+/// coverage of the outline of the glyph with its origin at `origin`
+fn outline(typeface: &Typeface, glyph: u16, size: f32, origin: Point<f32>) -> Vec<LetterPixel> {
+    let glyph = typeface
+        .font
+        .glyph(GlyphId(glyph))
+        .scaled(Scale::uniform(size))
+        .positioned(origin);
+    let mut pixels = vec![];
+    if let Some(bounds) = glyph.pixel_bounding_box() {
+        glyph.draw(|x, y, v| {
+            pixels.push(LetterPixel(
+                bounds.min.x + x as i32,
+                bounds.min.y + y as i32,
+                v,
+            ))
+        });
+    }
+    pixels
+}
+
+/// shapes the content with the OpenType tables of the font, which applies
+/// ligatures, kerning and the joining of complex scripts. the glyphs are
+/// returned in logical order
+fn shape(
+    typeface: &Typeface,
+    content: &str,
+    size: f32,
+    baseline: f32,
+    rtl: bool,
+) -> Vec<ShapedGlyph> {
+    let mut buffer = UnicodeBuffer::new();
+    buffer.push_str(content);
+    buffer.guess_segment_properties();
+    buffer.set_direction(match rtl {
+        true => rustybuzz::Direction::RightToLeft,
+        false => rustybuzz::Direction::LeftToRight,
+    });
+    let output = rustybuzz::shape(&typeface.face, &[], buffer);
+    // the same factor rusttype scales the outlines by
+    let v_metrics = typeface.font.v_metrics_unscaled();
+    let factor = size / (v_metrics.ascent - v_metrics.descent);
+    let pixels_per_em = factor * typeface.face.units_per_em() as f32;
+    let mut glyphs: Vec<_> = output
+        .glyph_infos()
+        .iter()
+        .zip(output.glyph_positions())
+        .map(|(info, position)| {
+            let id = info.glyph_id as u16;
+            let origin = point(
+                position.x_offset as f32 * factor,
+                baseline - position.y_offset as f32 * factor,
+            );
+            let mut pixels = vec![];
+            let mut color_pixels = vec![];
+            let mut path = None;
+            // color emoji come as bitmaps or as layers of outlines
+            if let Some(bitmap) = typeface.bitmap(id, pixels_per_em) {
+                let (left, top) = (
+                    (origin.x + bitmap.left).round() as i32,
+                    (origin.y + bitmap.top).round() as i32,
+                );
+                for (x, y, rgba) in bitmap.image.enumerate_pixels() {
+                    let [r, g, b, a] = rgba.0;
+                    if a > 0 {
+                        color_pixels.push(ColorPixel(
+                            left + x as i32,
+                            top + y as i32,
+                            GlyphColor::Rgba(color::Rgba(r, g, b, a)),
+                        ));
+                    }
+                }
+            } else if let Some(layers) = typeface.color_layers(id) {
+                // bottom up in the same pixels, the layers in the color of the
+                // text included
+                for (layer, color) in layers {
+                    let coverage = outline(typeface, layer, size, origin);
+                    color_pixels.extend(coverage.into_iter().map(|LetterPixel(x, y, v)| {
+                        let color = match color {
+                            Some(color::Rgba(r, g, b, a)) => {
+                                GlyphColor::Rgba(color::Rgba(r, g, b, (a as f32 * v).round() as u8))
+                            }
+                            None => GlyphColor::Text(v),
+                        };
+                        ColorPixel(x, y, color)
+                    }));
+                }
+            } else {
+                pixels = outline(typeface, id, size, origin);
+                path = outline_path(typeface, id, size, origin);
+            }
+            ShapedGlyph {
+                index: info.cluster as usize,
+                advance: position.x_advance as f32 * factor,
+                pixels,
+                color_pixels,
+                path,
+            }
+        })
+        .collect();
+    if rtl {
+        glyphs.reverse();
+    }
+    glyphs
+}
+
+/// drops the whitespace hanging at the end of the row and reorders the rest
+/// visually by the embedding levels of the glyphs
+fn reorder(mut letters: Vec<LetterGlyph>) -> Vec<LetterGlyph> {
+    while letters.last().is_some_and(|letter| letter.whitespace) {
+        letters.pop();
+    }
+    let levels: Vec<Level> = letters.iter().map(|letter| letter.level).collect();
+    let mut letters: Vec<_> = letters.into_iter().map(Some).collect();
+    BidiInfo::reorder_visual(&levels)
+        .into_iter()
+        .filter_map(|i| letters[i].take())
+        .collect()
+}
+
+/// spreads the space left in the row over its whitespace
+fn justify_row(letters: &mut [LetterGlyph], max_width: f32) {
+    let free = max_width - row_width(letters);
+    let spaces = letters.iter().filter(|letter| letter.whitespace).count();
+    if free <= 0. || spaces == 0 {
+        return;
+    }
+    for letter in letters.iter_mut().filter(|letter| letter.whitespace) {
+        letter.advance += free / spaces as f32;
+    }
+}
+
+fn row_width(letters: &[LetterGlyph]) -> f32 {
+    letters.iter().map(|letter| letter.advance).sum()
+}
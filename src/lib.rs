@@ -16,7 +16,14 @@ use serde::Deserialize;
 #[wasm_bindgen]
 pub fn draw(val: String) -> Result<Vec<u8>, JsValue> {
     let cvs = CanvasConfiguration::try_from(val)?.build()?;
-    return Ok(cvs.export()?);
+    Ok(cvs.export()?)
+}
+
+/// resolved `{ x, y, width, height }` of every graphic with an `id`, as JSON
+#[wasm_bindgen]
+pub fn layout(val: String) -> Result<String, JsValue> {
+    let bounds = CanvasConfiguration::try_from(val)?.layout()?;
+    Ok(serde_json::to_string(&bounds).map_err(utils::error_mapper)?)
 }
 
 // #[wasm_bindgen]
@@ -65,28 +72,1090 @@ mod tests {
 
     use image::{DynamicImage, EncodableLayout, Rgba};
     use rusttype::{point, Font, Scale};
+    use tiny_skia::PremultipliedColorU8;
 
     use super::*;
 
     #[test]
     fn it_works() {
         let s = r#"{
-            "size":[1200,800],
-            "background":{"type":"Rgba","value":[255,255,0,200]},
-            "graphics":[
-                { "type": "Rectangle", "value": { "corner": [24, 24, 24, 24], "color": { "type": "Rgba", "value": [255, 0, 0, 120] }, "position": [30, 30], "size": [400, 300] } }
-            ]
+            "size": { "width": 1200, "height": 800 },
+            "background": { "type": "Rgba", "value": [255, 255, 0, 200] },
+            "graphic": {
+                "children": [
+                    { "type": "Container", "value": { "corner": [24, 24, 24, 24], "color": { "type": "Rgba", "value": [255, 0, 0, 120] }, "position": { "x": 30, "y": 30 }, "size": { "width": 400, "height": 300 } } }
+                ]
+            }
         }"#;
         let buff = draw(s.to_string()).unwrap();
         save(&buff);
     }
 
-    fn save(buff: &Vec<u8>) {
+    #[test]
+    fn canvas_size_is_pinned() {
+        let s = r#"{
+            "size": { "width": 300, "height": 200 },
+            "background": { "type": "Rgba", "value": [255, 255, 255, 255] },
+            "graphic": {
+                "children": [
+                    { "type": "Container", "value": { "color": { "type": "Rgba", "value": [255, 0, 0, 255] }, "position": { "x": 250, "y": 20 }, "size": { "width": 100, "height": 100 } } }
+                ]
+            }
+        }"#;
+        let cvs = build(s);
+        assert_eq!((cvs.pixmap.width(), cvs.pixmap.height()), (300, 200));
+        let pixel = |x: u32, y: u32| cvs.pixmap.pixel(x, y).unwrap();
+        assert_eq!(pixel(10, 10).red(), 255);
+        assert_eq!(pixel(10, 10).green(), 255);
+        assert_eq!(pixel(270, 50).green(), 0);
+        assert_eq!(pixel(270, 150).green(), 255);
+    }
+
+    #[test]
+    fn row_flow() {
+        let s = r#"{
+            "size": { "width": 300, "height": 100 },
+            "graphic": {
+                "direction": "Row",
+                "gap": 20,
+                "justify": "Center",
+                "align_items": "End",
+                "size": { "height": 100 },
+                "children": [
+                    { "type": "Container", "value": { "color": { "type": "Rgba", "value": [255, 0, 0, 255] }, "size": { "width": 40, "height": 40 } } },
+                    { "type": "Container", "value": { "color": { "type": "Rgba", "value": [0, 0, 255, 255] }, "size": { "width": 40, "height": 80 } } }
+                ]
+            }
+        }"#;
+        let cvs = build(s);
+        let alpha = |x: u32, y: u32| cvs.pixmap.pixel(x, y).unwrap().alpha();
+        // 300 - (40 + 20 + 40) leaves 100px on each side
+        assert_eq!(alpha(99, 90), 0);
+        assert_eq!(cvs.pixmap.pixel(100, 90).unwrap().red(), 255);
+        assert_eq!(alpha(120, 50), 0);
+        assert_eq!(cvs.pixmap.pixel(160, 30).unwrap().blue(), 255);
+        assert_eq!(alpha(200, 90), 0);
+    }
+
+    #[test]
+    fn arc_modes_and_sweeps() {
+        let arc = |value: &str| {
+            build(&format!(
+                r#"{{
+                    "size": {{ "width": 100, "height": 100 }},
+                    "graphic": {{ "children": [
+                        {{ "type": "Arc", "value": {{ "radius": 40, "position": {{ "x": 10, "y": 10 }}, "color": {{ "type": "Rgba", "value": [255, 0, 0, 255] }}, {value} }} }}
+                    ] }}
+                }}"#
+            ))
+        };
+        // a full stroke is a ring from radius 30 to 40 around (50, 50)
+        let cvs = arc(r#""width": 10"#);
+        let alpha = |x: u32, y: u32| cvs.pixmap.pixel(x, y).unwrap().alpha();
+        assert_eq!(alpha(50, 15), 255);
+        assert_eq!(alpha(85, 50), 255);
+        assert_eq!(alpha(50, 85), 255);
+        assert_eq!(alpha(50, 50), 0);
+        assert_eq!(alpha(50, 5), 0);
+        // 0 is 12 o'clock and the slice grows clockwise to 3 o'clock
+        let cvs = arc(r#""width": 10, "mode": "Fill", "start_angle": 0, "end_angle": 90"#);
+        let alpha = |x: u32, y: u32| cvs.pixmap.pixel(x, y).unwrap().alpha();
+        assert_eq!(alpha(70, 30), 255);
+        assert_eq!(alpha(30, 30), 0);
+        assert_eq!(alpha(30, 70), 0);
+        assert_eq!(alpha(70, 70), 0);
+        // a negative end angle sweeps counter-clockwise to 9 o'clock
+        let cvs = arc(r#""width": 10, "mode": "Fill", "start_angle": 0, "end_angle": -90"#);
+        let alpha = |x: u32, y: u32| cvs.pixmap.pixel(x, y).unwrap().alpha();
+        assert_eq!(alpha(30, 30), 255);
+        assert_eq!(alpha(70, 30), 0);
+        assert_eq!(alpha(30, 70), 0);
+        // a stroked quarter leaves the rest of the ring empty
+        let cvs = arc(r#""width": 10, "start_angle": 90, "end_angle": 180"#);
+        let alpha = |x: u32, y: u32| cvs.pixmap.pixel(x, y).unwrap().alpha();
+        assert_eq!(alpha(70, 80), 255);
+        assert_eq!(alpha(50, 15), 0);
+        assert_eq!(alpha(15, 50), 0);
+        // gradients are mapped across the square of the arc
+        let cvs = build(
+            r#"{
+                "size": { "width": 100, "height": 100 },
+                "graphic": { "children": [
+                    { "type": "Arc", "value": { "radius": 40, "width": 10, "mode": "Fill", "position": { "x": 10, "y": 10 },
+                        "color": { "type": "Gradient", "value": { "angle": 90, "stops": [
+                            { "position": { "type": "Percent", "value": 0 }, "color": [255, 0, 0, 255] },
+                            { "position": { "type": "Percent", "value": 1 }, "color": [0, 0, 255, 255] }
+                        ] } },
+                        "shadow": { "x": 0, "y": 8, "blur": 0, "color": [0, 255, 0, 255] } } }
+                ] }
+            }"#,
+        );
+        let pixel = |x: u32, y: u32| cvs.pixmap.pixel(x, y).unwrap();
+        assert!(pixel(15, 50).red() > pixel(15, 50).blue());
+        assert!(pixel(85, 50).blue() > pixel(85, 50).red());
+        // the shadow is cast below the disc
+        assert!(pixel(50, 94).green() > 0);
+    }
+
+    #[test]
+    fn flex_grow_and_wrap() {
+        let s = r#"{
+            "size": { "width": 300, "height": 100 },
+            "graphic": {
+                "direction": "Row",
+                "wrap": true,
+                "children": [
+                    { "type": "Container", "value": { "color": { "type": "Rgba", "value": [255, 0, 0, 255] }, "size": { "width": 40, "height": 40 } } },
+                    { "type": "Container", "value": { "color": { "type": "Rgba", "value": [0, 255, 0, 255] }, "flex_grow": 1, "align_self": "Stretch" } },
+                    { "type": "Container", "value": { "color": { "type": "Rgba", "value": [0, 0, 255, 255] }, "size": { "width": 60, "height": 20 } } },
+                    { "type": "Container", "value": { "color": { "type": "Rgba", "value": [0, 0, 0, 255] }, "size": { "width": 260, "height": 20 } } }
+                ]
+            }
+        }"#;
+        let cvs = build(s);
+        let pixel = |x: u32, y: u32| cvs.pixmap.pixel(x, y).unwrap();
+        assert_eq!(pixel(45, 30).green(), 255);
+        assert_eq!(pixel(239, 30).green(), 255);
+        assert_eq!(pixel(245, 10).blue(), 255);
+        assert_eq!(pixel(245, 30).alpha(), 0);
+        // the last child does not fit and wraps below the first line
+        assert_eq!(pixel(10, 50).alpha(), 255);
+        assert_eq!(pixel(270, 50).alpha(), 0);
+    }
+
+    #[test]
+    fn stretch_keeps_a_fixed_cross_size() {
+        let s = r#"{
+            "size": { "width": 200, "height": 200 },
+            "graphic": {
+                "size": { "width": 200 },
+                "align_items": "Stretch",
+                "children": [
+                    { "type": "Container", "value": { "id": "fixed", "size": { "width": 50, "height": 20 } } },
+                    { "type": "Container", "value": { "id": "auto", "size": { "height": 20 } } }
+                ]
+            }
+        }"#;
+        let bounds = layout_of(s);
+        assert_eq!(bounds["fixed"]["width"], 50.);
+        assert_eq!(bounds["auto"]["width"], 200.);
+        let s = r#"{
+            "size": { "width": 200, "height": 100 },
+            "graphic": {
+                "direction": "Row",
+                "size": { "width": 200, "height": 100 },
+                "align_items": "Stretch",
+                "children": [
+                    { "type": "Container", "value": { "id": "fixed", "size": { "width": 50, "height": 30 } } },
+                    { "type": "Container", "value": { "id": "auto", "size": { "width": 50 } } }
+                ]
+            }
+        }"#;
+        let bounds = layout_of(s);
+        assert_eq!(bounds["fixed"]["height"], 30.);
+        assert_eq!(bounds["auto"]["height"], 100.);
+    }
+
+    #[test]
+    fn grid_tracks() {
+        let s = r#"{
+            "size": { "width": 300, "height": 100 },
+            "graphic": {
+                "grid": {
+                    "columns": [{ "type": "Px", "value": 50 }, { "type": "Fr", "value": 1 }, { "type": "Fr", "value": 2 }],
+                    "rows": [{ "type": "Px", "value": 40 }]
+                },
+                "gap": 10,
+                "children": [
+                    { "type": "Container", "value": { "color": { "type": "Rgba", "value": [255, 0, 0, 255] } } },
+                    { "type": "Container", "value": { "color": { "type": "Rgba", "value": [0, 255, 0, 255] } } },
+                    { "type": "Container", "value": { "color": { "type": "Rgba", "value": [0, 0, 255, 255] }, "grid_row": { "start": 2 }, "grid_column": { "start": 2, "span": 2 }, "size": { "height": 20 } } }
+                ]
+            }
+        }"#;
+        let cvs = build(s);
+        let pixel = |x: u32, y: u32| cvs.pixmap.pixel(x, y).unwrap();
+        // 300 - 50 - 2 * 10 leaves 230px, split 1:2 between the fractions
+        assert_eq!(pixel(49, 39).red(), 255);
+        assert_eq!(pixel(55, 20).alpha(), 0);
+        assert_eq!(pixel(60, 20).green(), 255);
+        assert_eq!(pixel(135, 20).green(), 255);
+        assert_eq!(pixel(140, 20).alpha(), 0);
+        assert_eq!(pixel(60, 50).blue(), 255);
+        assert_eq!(pixel(299, 69).blue(), 255);
+        assert_eq!(pixel(30, 50).alpha(), 0);
+    }
+
+    #[test]
+    fn grid_places_every_kind_of_graphic() {
+        let s = r#"{
+            "size": { "width": 100, "height": 100 },
+            "graphic": {
+                "grid": {
+                    "columns": [{ "type": "Px", "value": 50 }, { "type": "Px", "value": 50 }],
+                    "rows": [{ "type": "Px", "value": 50 }, { "type": "Px", "value": 50 }]
+                },
+                "children": [
+                    { "type": "Arc", "value": { "radius": 20, "width": 1, "mode": "Fill", "color": [0, 0, 255, 255], "grid_column": { "start": 2 }, "grid_row": { "start": 2 } } },
+                    { "type": "Container", "value": { "color": [255, 0, 0, 255], "size": { "width": 10, "height": 10 } } }
+                ]
+            }
+        }"#;
+        let cvs = build(s);
+        let pixel = |x: u32, y: u32| cvs.pixmap.pixel(x, y).unwrap();
+        // lines are 1 based like css, the arc takes the second row and column
+        assert_eq!(pixel(70, 70).blue(), 255);
+        assert_eq!(pixel(20, 70).alpha(), 0);
+        assert_eq!(pixel(70, 20).alpha(), 0);
+        // the container is auto placed into the first free cell
+        assert_eq!(pixel(5, 5).red(), 255);
+    }
+
+    #[test]
+    fn auto_height_is_laid_out_before_painting() {
+        let s = r#"{
+            "graphic": {
+                "size": { "width": 100 },
+                "padding": [10, 10, 10, 10],
+                "color": { "type": "Rgba", "value": [255, 0, 0, 255] },
+                "children": [
+                    { "type": "Container", "value": { "color": { "type": "Rgba", "value": [0, 0, 255, 255] }, "size": { "width": 50, "height": 30 } } }
+                ]
+            }
+        }"#;
+        let cvs = build(s);
+        assert_eq!((cvs.pixmap.width(), cvs.pixmap.height()), (100, 50));
+        let pixel = |x: u32, y: u32| cvs.pixmap.pixel(x, y).unwrap();
+        assert_eq!(pixel(20, 20).blue(), 255);
+        assert_eq!(pixel(50, 45).red(), 255);
+    }
+
+    #[test]
+    fn layout_bounds_by_id() {
+        let s = r#"{
+            "size": { "width": 300, "height": 100 },
+            "graphic": {
+                "id": "root",
+                "direction": "Row",
+                "gap": 10,
+                "padding": [5, 5, 5, 5],
+                "children": [
+                    { "type": "Container", "value": { "id": "a", "size": { "width": 40, "height": 40 } } },
+                    { "type": "Container", "value": { "size": { "width": 40, "height": 40 }, "children": [
+                        { "type": "Line", "value": { "id": "b", "from": { "x": 0, "y": 10 }, "to": { "x": 40, "y": 10 }, "width": 1, "color": [0, 0, 0, 255] } }
+                    ] } }
+                ]
+            }
+        }"#;
+        let bounds = layout_of(s);
+        assert_eq!(bounds.as_object().unwrap().len(), 3);
+        assert_eq!(bounds["root"]["width"], 300.);
+        assert_eq!(bounds["a"]["x"], 5.);
+        assert_eq!(bounds["b"]["x"], 55.);
+        assert_eq!(bounds["b"]["y"], 15.);
+        assert_eq!(bounds["b"]["width"], 40.);
+    }
+
+    #[test]
+    fn vertical_align_and_anchors() {
+        let s = r#"{
+            "size": { "width": 200, "height": 100 },
+            "graphic": {
+                "size": { "width": 200, "height": 100 },
+                "children": [
+                    { "type": "Container", "value": { "id": "badge", "position": { "right": 10, "y": 5 }, "size": { "width": 20, "height": 20 } } },
+                    { "type": "Container", "value": { "id": "footer", "position": { "bottom": 0 }, "size": { "width": 200, "height": 30 } } },
+                    { "type": "Container", "value": { "id": "middle", "vertical_align": "Middle", "align": "Center", "size": { "width": 40, "height": 40 } } }
+                ]
+            }
+        }"#;
+        let bounds = layout_of(s);
+        assert_eq!(bounds["badge"]["x"], 170.);
+        assert_eq!(bounds["badge"]["y"], 5.);
+        assert_eq!(bounds["footer"]["y"], 70.);
+        assert_eq!(bounds["middle"]["x"], 80.);
+        assert_eq!(bounds["middle"]["y"], 30.);
+    }
+
+    #[test]
+    fn viewport_is_per_layout() {
+        let grown = r#"{
+            "graphic": {
+                "size": { "width": 200, "height": 100 },
+                "children": [
+                    { "type": "Container", "value": { "id": "item", "margin": [0, 0, 0, "10vw"], "size": { "width": 10, "height": 10 } } }
+                ]
+            }
+        }"#;
+        let sized = r#"{
+            "size": { "width": 400, "height": 100 },
+            "graphic": {
+                "children": [
+                    { "type": "Container", "value": { "id": "item", "margin": [0, 0, 0, "10vw"], "size": { "width": 10, "height": 10 } } }
+                ]
+            }
+        }"#;
+        // the root graphic is the viewport of a grown canvas, and one layout
+        // does not leak into the next
+        assert_eq!(layout_of(grown)["item"]["x"], 20.);
+        assert_eq!(layout_of(sized)["item"]["x"], 40.);
+        assert_eq!(layout_of(grown)["item"]["x"], 20.);
+    }
+
+    #[test]
+    fn position_accepts_an_xy_pair() {
+        let s = r#"{
+            "size": { "width": 200, "height": 100 },
+            "graphic": {
+                "size": { "width": 200, "height": 100 },
+                "children": [
+                    { "type": "Container", "value": { "id": "pair", "position_mode": "Absolute", "position": [10, 20], "size": { "width": 10, "height": 10 } } },
+                    { "type": "Container", "value": { "id": "lengths", "position_mode": "Absolute", "position": ["10%", "50%"], "size": { "width": 10, "height": 10 } } }
+                ]
+            }
+        }"#;
+        let bounds = layout_of(s);
+        assert_eq!(bounds["pair"]["x"], 10.);
+        assert_eq!(bounds["pair"]["y"], 20.);
+        assert_eq!(bounds["lengths"]["x"], 20.);
+        assert_eq!(bounds["lengths"]["y"], 50.);
+        let position = |value: &str| serde_json::from_str::<metrics::Position>(value);
+        assert!(position("[1, 2, 3]").is_err());
+        // a bad field of the map form reports that field
+        let error = position(r#"{ "x": "left" }"#).unwrap_err().to_string();
+        assert!(error.contains("invalid type"), "{error}");
+    }
+
+    #[test]
+    fn relative_units() {
+        let s = r#"{
+            "size": { "width": 400, "height": 200 },
+            "graphic": {
+                "size": { "width": "100vw", "height": "100vh" },
+                "padding": ["10%", 0, 0, "5%"],
+                "children": [
+                    { "type": "Container", "value": { "id": "half", "size": { "width": "50%", "height": "25vh" } } },
+                    { "type": "Container", "value": { "id": "anchored", "position": { "x": "10vw", "bottom": "10%" }, "size": { "width": "20px", "height": 20 } } }
+                ]
+            }
+        }"#;
+        let bounds = layout_of(s);
+        assert_eq!(bounds["half"]["x"], 20.);
+        assert_eq!(bounds["half"]["y"], 20.);
+        assert_eq!(bounds["half"]["width"], 190.);
+        assert_eq!(bounds["half"]["height"], 50.);
+        assert_eq!(bounds["anchored"]["x"], 60.);
+        assert_eq!(bounds["anchored"]["y"], 162.);
+    }
+
+    #[test]
+    fn deep_rows_are_measured_once() {
+        use graphic::container::FLOWS;
+        let flows = |depth: usize| {
+            let mut node =
+                r#"{ "type": "Container", "value": { "id": "leaf", "size": { "width": 10, "height": 10 } } }"#
+                    .to_string();
+            for _ in 0..depth {
+                node = format!(
+                    r#"{{ "type": "Container", "value": {{ "direction": "Row", "padding": [1, 1, 1, 1], "children": [{node}] }} }}"#
+                );
+            }
+            let s = format!(
+                r#"{{ "size": {{ "width": 400, "height": 400 }}, "graphic": {{ "direction": "Row", "children": [{node}] }} }}"#
+            );
+            FLOWS.with(|flows| flows.set(0));
+            let bounds = layout_of(&s);
+            assert_eq!(bounds["leaf"]["x"], depth as f64);
+            assert_eq!(bounds["leaf"]["y"], depth as f64);
+            FLOWS.with(|flows| flows.get())
+        };
+        // every level adds the same number of flows
+        let (shallow, deep, deeper) = (flows(10), flows(20), flows(30));
+        assert_eq!(deeper - deep, deep - shallow);
+    }
+
+    #[test]
+    fn size_constraints() {
+        let s = r#"{
+            "size": { "width": 400, "height": 400 },
+            "graphic": {
+                "size": { "width": 400 },
+                "children": [
+                    { "type": "Container", "value": { "id": "clamped", "max_width": 200, "min_height": 50 } },
+                    { "type": "Container", "value": { "id": "video", "size": { "width": 160 }, "aspect_ratio": 1.6 } },
+                    { "type": "Container", "value": { "id": "long", "max_height": 40, "children": [
+                        { "type": "Container", "value": { "size": { "height": 80 } } }
+                    ] } }
+                ]
+            }
+        }"#;
+        let bounds = layout_of(s);
+        assert_eq!(bounds["clamped"]["width"], 200.);
+        assert_eq!(bounds["clamped"]["height"], 50.);
+        assert_eq!(bounds["video"]["y"], 50.);
+        assert_eq!(bounds["video"]["height"], 100.);
+        assert_eq!(bounds["long"]["y"], 150.);
+        assert_eq!(bounds["long"]["height"], 40.);
+    }
+
+    #[test]
+    fn margins_in_flow() {
+        let s = r#"{
+            "size": { "width": 200, "height": 200 },
+            "graphic": {
+                "id": "root",
+                "size": { "width": 200 },
+                "children": [
+                    { "type": "Container", "value": { "id": "a", "size": { "height": 20 }, "margin": [0, 0, 10, 0] } },
+                    { "type": "Container", "value": { "id": "b", "size": { "height": 20 }, "margin": [5, 10, 0, 15] } }
+                ]
+            }
+        }"#;
+        let bounds = layout_of(s);
+        assert_eq!(bounds["b"]["x"], 15.);
+        assert_eq!(bounds["b"]["y"], 35.);
+        assert_eq!(bounds["b"]["width"], 175.);
+        assert_eq!(bounds["root"]["height"], 55.);
+    }
+
+    #[test]
+    fn absolute_children_and_z_index() {
+        let s = r#"{
+            "graphic": {
+                "size": { "width": 200 },
+                "children": [
+                    { "type": "Container", "value": { "position_mode": "Absolute", "z_index": 1, "position": { "right": 0, "y": 0 }, "size": { "width": 20, "height": 20 }, "color": { "type": "Rgba", "value": [255, 0, 0, 255] } } },
+                    { "type": "Container", "value": { "size": { "height": 30 }, "color": { "type": "Rgba", "value": [0, 0, 255, 255] } } }
+                ]
+            }
+        }"#;
+        let cvs = build(s);
+        assert_eq!((cvs.pixmap.width(), cvs.pixmap.height()), (200, 30));
+        let pixel = |x: u32, y: u32| cvs.pixmap.pixel(x, y).unwrap();
+        assert_eq!(pixel(10, 5).blue(), 255);
+        assert_eq!(pixel(190, 10).red(), 255);
+        assert_eq!(pixel(190, 10).blue(), 0);
+    }
+
+    #[test]
+    fn spans_wrap_and_truncate_together() {
+        init_font();
+        let s = r#"{
+            "size": { "width": 120, "height": 200 },
+            "graphic": {
+                "size": { "width": 120 },
+                "children": [
+                    { "type": "Text", "value": { "id": "text", "color": [80, 80, 80, 255], "size": 20, "max_rows": 2, "suffix": "...", "spans": [
+                        { "content": "$199", "size": 40, "color": [255, 0, 0, 255], "decoration": { "line": "LineThrough" } },
+                        { "content": "only today, while the stock lasts" }
+                    ] } }
+                ]
+            }
+        }"#;
+        let bounds = layout_of(s);
+        // the line height follows the largest span
+        assert_eq!(bounds["text"]["height"], 80.);
+        assert!(bounds["text"]["width"].as_f64().unwrap() <= 120.);
+        build(s);
+    }
+
+    #[test]
+    fn line_breaks_between_words() {
+        use graphic::{text::Text, Layout};
+        use tiny_skia::Rect;
+        init_font();
+        let mut text: Text = serde_json::from_str(
+            r#"{ "content": "wrap whole words\n\nonly", "color": [0, 0, 0, 255], "size": 20, "max_rows": 10 }"#,
+        )
+        .unwrap();
+        text.layout(
+            xywh_rect!(0., 0., 100., 400.),
+            xywh_rect!(0., 0., 100., 400.),
+            metrics::Viewport::default(),
+        )
+        .unwrap();
+        let content = text.text();
+        let rows: Vec<String> = text
+            .allocated()
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|letter| &content[letter.index..=letter.index])
+                    .collect()
+            })
+            .collect();
+        // the blank line is kept and no word is split
+        assert_eq!(rows, ["wrap", "whole", "words", "", "only"]);
+    }
+
+    #[test]
+    fn text_width_follows_advances() {
+        init_font();
+        let width = |content: &str| text_width(400., &format!(r#""content": "{content}""#));
+        // spaces keep their advance unless they hang at the end of a row
+        assert!(width("hello world") > width("helloworld"));
+        assert_eq!(width("hello "), width("hello"));
+    }
+
+    #[test]
+    fn shaping_applies_ligatures() {
+        use graphic::{text::Text, Layout};
+        use tiny_skia::Rect;
+        init_font();
+        let mut text: Text =
+            serde_json::from_str(r#"{ "content": "office", "color": [0, 0, 0, 255], "size": 20 }"#)
+                .unwrap();
+        text.layout(
+            xywh_rect!(0., 0., 200., 40.),
+            xywh_rect!(0., 0., 200., 40.),
+            metrics::Viewport::default(),
+        )
+        .unwrap();
+        let indexes: Vec<usize> = text.allocated()[0]
+            .iter()
+            .map(|letter| letter.index)
+            .collect();
+        // "fi" is a single glyph of the cluster at 2
+        assert_eq!(indexes, [0, 1, 2, 4, 5]);
+    }
+
+    #[test]
+    fn bidi_rows_are_reordered() {
+        use graphic::{text::Text, Layout};
+        use tiny_skia::Rect;
+        init_font();
+        let indexes = |value: &str| {
+            let mut text: Text = serde_json::from_str(value).unwrap();
+            let bounds = text
+                .layout(
+                    xywh_rect!(0., 0., 200., 40.),
+                    xywh_rect!(0., 0., 200., 40.),
+                    metrics::Viewport::default(),
+                )
+                .unwrap();
+            let indexes: Vec<usize> = text.allocated()[0]
+                .iter()
+                .map(|letter| letter.index)
+                .collect();
+            (indexes, bounds)
+        };
+        let (ltr, _) = indexes(r#"{ "content": "ab אב", "color": [0, 0, 0, 255], "size": 20 }"#);
+        assert_eq!(ltr, [0, 1, 2, 5, 3]);
+        // left is the start of a right to left paragraph
+        let (rtl, bounds) = indexes(
+            r#"{ "content": "ab אב", "color": [0, 0, 0, 255], "size": 20, "direction": "Rtl", "align": "Left" }"#,
+        );
+        assert_eq!(rtl, [5, 3, 2, 0, 1]);
+        assert_eq!(bounds.right(), 200.);
+        // without an align the rows still start at the right edge
+        let (unset, unset_bounds) = indexes(
+            r#"{ "content": "ab אב", "color": [0, 0, 0, 255], "size": 20, "direction": "Rtl" }"#,
+        );
+        assert_eq!(unset, rtl);
+        assert_eq!(unset_bounds, bounds);
+        let (_, hebrew) = indexes(r#"{ "content": "אב", "color": [0, 0, 0, 255], "size": 20 }"#);
+        assert_eq!(hebrew.right(), 200.);
+    }
+
+    #[test]
+    fn font_falls_back_in_order() {
+        init_font();
+        let width =
+            |font: &str| text_width(400., &format!(r#""content": "fallback", "font": {font}"#));
+        // families missing from the font set are skipped
+        assert!(width(r#""""#) > 0.);
+        assert_eq!(width(r#"["missing", ""]"#), width(r#""""#));
+    }
+
+    #[test]
+    fn colr_layers_are_read_from_the_tables() {
+        let be16 = |v: u16| v.to_be_bytes().to_vec();
+        let be32 = |v: u32| v.to_be_bytes().to_vec();
+        // two base glyphs sorted by id, glyph 7 has a palette layer and a
+        // layer in the color of the text
+        let colr: Vec<u8> = [
+            be16(0),
+            be16(2),
+            be32(14),
+            be32(26),
+            be16(3),
+            [be16(3), be16(0), be16(1)].concat(),
+            [be16(7), be16(1), be16(2)].concat(),
+            [be16(12), be16(1)].concat(),
+            [be16(10), be16(0)].concat(),
+            [be16(11), be16(0xFFFF)].concat(),
+        ]
+        .concat();
+        // one palette of two colors stored as bgra
+        let cpal: Vec<u8> = [
+            be16(0),
+            be16(2),
+            be16(1),
+            be16(2),
+            be32(14),
+            be16(0),
+            vec![0, 0, 255, 255, 255, 0, 0, 128],
+        ]
+        .concat();
+        assert_eq!(
+            font::colr_layers(&colr, &cpal, 7),
+            Some(vec![(10, Some(color::Rgba(255, 0, 0, 255))), (11, None)])
+        );
+        assert_eq!(
+            font::colr_layers(&colr, &cpal, 3),
+            Some(vec![(12, Some(color::Rgba(0, 0, 255, 128)))])
+        );
+        // glyphs without a base record have no layers
+        assert_eq!(font::colr_layers(&colr, &cpal, 5), None);
+        // tables cut short are not read out of bounds
+        assert_eq!(font::colr_layers(&colr[..30], &cpal, 7), None);
+        assert_eq!(font::colr_layers(&colr, &cpal[..16], 7), None);
+        assert_eq!(font::colr_layers(&colr[..6], &cpal, 7), None);
+    }
+
+    #[test]
+    fn cbdt_bitmaps_are_scaled_from_the_strike() {
+        let data = include_bytes!("../assets/fixtures/cbdt.ttf").to_vec();
+        font::init_fonts([("cbdt".to_string(), data)].into()).unwrap();
+        let typeface = font::get_typefaces(&["cbdt".to_string()])[0];
+        // glyph 1 is a 136x128 png in a strike of 94 pixels per em
+        let bitmap = typeface.bitmap(1, 94.).unwrap();
+        assert_eq!(bitmap.image.dimensions(), (136, 128));
+        assert_eq!((bitmap.left, bitmap.top), (0., -100.));
+        let bitmap = typeface.bitmap(1, 47.).unwrap();
+        assert_eq!(bitmap.image.dimensions(), (68, 64));
+        assert_eq!((bitmap.left, bitmap.top), (0., -50.));
+        assert!(typeface.bitmap(0, 94.).is_none());
+        // a size is decoded once
+        assert!(std::rc::Rc::ptr_eq(
+            &bitmap,
+            &typeface.bitmap(1, 47.).unwrap()
+        ));
+    }
+
+    #[test]
+    fn text_draws_color_glyphs() {
+        let fonts = [
+            (
+                "cbdt",
+                include_bytes!("../assets/fixtures/cbdt.ttf").to_vec(),
+            ),
+            // the COLR test font of harfbuzz with the bottom of its three
+            // layers in the color of the text, a red and a yellow band are
+            // painted over it
+            (
+                "colr",
+                include_bytes!("../assets/fixtures/colr.ttf").to_vec(),
+            ),
+        ];
+        font::init_fonts(
+            fonts
+                .map(|(family, data)| (family.to_string(), data))
+                .into(),
+        )
+        .unwrap();
+        let text = |font: &str, content: &str, color: &str| {
+            build(&format!(
+                r#"{{
+                    "size": {{ "width": 100, "height": 60 }},
+                    "graphic": {{ "children": [
+                        {{ "type": "Text", "value": {{ "content": "{content}", "font": "{font}", "color": {color}, "size": 40 }} }}
+                    ] }}
+                }}"#
+            ))
+        };
+        let count = |cvs: &canvas::Canvas, rgb: fn(u8, u8, u8) -> bool| {
+            cvs.pixmap
+                .pixels()
+                .iter()
+                .map(|pixel| pixel.demultiply())
+                .filter(|pixel| {
+                    pixel.alpha() == 255 && rgb(pixel.red(), pixel.green(), pixel.blue())
+                })
+                .count()
+        };
+        // the bitmap is drawn even though the text itself is transparent
+        let cvs = text("cbdt", "\u{1F42F}", "[0, 0, 0, 0]");
+        assert!(count(&cvs, |_, _, _| true) > 100);
+        let flag = "\u{1F3F4}\u{E0064}\u{E0065}\u{E007F}";
+        let cvs = text("colr", flag, "[0, 0, 255, 255]");
+        let blue = count(&cvs, |r, g, b| r == 0 && g == 0 && b == 255);
+        let red = count(&cvs, |r, g, b| r == 255 && g == 0 && b == 0);
+        let yellow = count(&cvs, |r, g, b| r == 255 && g == 204 && b == 0);
+        assert!(
+            blue > 50 && red > 50 && yellow > 50,
+            "{blue} {red} {yellow}"
+        );
+        // a gradient text color is mapped across the layer in its color
+        let cvs = text(
+            "colr",
+            flag,
+            r#"{ "type": "Gradient", "value": { "angle": 90, "stops": [
+                { "position": { "type": "Percent", "value": 0 }, "color": [0, 0, 255, 255] },
+                { "position": { "type": "Percent", "value": 100 }, "color": [0, 255, 0, 255] }
+            ] } }"#,
+        );
+        assert!(count(&cvs, |r, g, b| r == 0 && g > 0 && b > 0) > 50);
+        assert!(count(&cvs, |r, g, b| r == 255 && g == 0 && b == 0) > 50);
+    }
+
+    #[test]
+    fn text_stroke_and_shadow() {
+        init_font();
+        let cvs = build(
+            r#"{
+                "size": { "width": 100, "height": 100 },
+                "graphic": { "children": [
+                    { "type": "Text", "value": { "content": "H", "color": [0, 0, 0, 255], "size": 40,
+                        "stroke": { "width": 2, "color": [255, 0, 0, 255] },
+                        "shadow": { "x": 0, "y": 50, "blur": 0, "color": [0, 0, 255, 255] } } }
+                ] }
+            }"#,
+        );
+        let outline = cvs
+            .pixmap
+            .pixels()
+            .iter()
+            .filter(|pixel| pixel.red() == 255 && pixel.alpha() == 255)
+            .count();
+        assert!(outline > 0);
+        // the shadow is cast below the text, outline included
+        let shadow = (50..100)
+            .flat_map(|y| (0..100).map(move |x| (x, y)))
+            .filter(|&(x, y)| cvs.pixmap.pixel(x, y).unwrap().blue() > 0)
+            .count();
+        assert!(shadow > 0);
+    }
+
+    #[test]
+    fn text_grows_an_unsized_canvas() {
+        init_font();
+        let cvs = build(
+            r#"{
+                "graphic": { "size": { "width": 200, "height": 60 }, "children": [
+                    { "type": "Text", "value": { "content": "HELLO", "color": [0, 0, 0, 255], "size": 40 } }
+                ] }
+            }"#,
+        );
+        let opaque = cvs
+            .pixmap
+            .pixels()
+            .iter()
+            .filter(|pixel| pixel.alpha() == 255)
+            .count();
+        assert!(opaque > 0);
+    }
+
+    #[test]
+    fn text_stroke_surrounds_fill_evenly() {
+        init_font();
+        let cvs = build(
+            r#"{
+                "size": { "width": 60, "height": 320 },
+                "graphic": { "children": [
+                    { "type": "Text", "value": { "content": "H\nH\nH\nH\nH\nH\nH", "color": [0, 0, 0, 255], "size": 30,
+                        "line_height": 40.7, "position": { "x": 10, "y": 10.6 },
+                        "stroke": { "width": 2, "color": [255, 0, 0, 255] } } }
+                ] }
+            }"#,
+        );
+        // bounding box of the pixels of a row which pass the filter
+        let bbox = |rows: std::ops::Range<u32>, filter: &dyn Fn(PremultipliedColorU8) -> bool| {
+            let points: Vec<(u32, u32)> = rows
+                .flat_map(|y| (0..60).map(move |x| (x, y)))
+                .filter(|&(x, y)| filter(cvs.pixmap.pixel(x, y).unwrap()))
+                .collect();
+            let xs = points.iter().map(|p| p.0);
+            let ys = points.iter().map(|p| p.1);
+            (
+                xs.clone().min().unwrap() as i32,
+                ys.clone().min().unwrap() as i32,
+                xs.max().unwrap() as i32,
+                ys.max().unwrap() as i32,
+            )
+        };
+        for row in 0..7 {
+            let top = (10.6 + row as f32 * 40.7) as u32;
+            let rows = top..top + 40;
+            let fill = bbox(rows.clone(), &|pixel| {
+                pixel.alpha() > 128 && pixel.red() < 128
+            });
+            let outline = bbox(rows, &|pixel| pixel.alpha() > 128);
+            let (left, top) = (fill.0 - outline.0, fill.1 - outline.1);
+            let (right, bottom) = (outline.2 - fill.2, outline.3 - fill.3);
+            assert!(left > 0 && top > 0, "row {row}");
+            assert!((left - right).abs() <= 1, "row {row}");
+            assert!((top - bottom).abs() <= 1, "row {row}");
+        }
+    }
+
+    #[test]
+    fn decoration_wraps_with_rows() {
+        init_font();
+        let cvs = build(
+            r#"{
+                "size": { "width": 100, "height": 60 },
+                "graphic": { "size": { "width": 70 }, "children": [
+                    { "type": "Text", "value": { "content": "under lined", "color": [0, 0, 0, 255], "size": 20, "line_height": 30,
+                        "decoration": { "line": "Underline", "style": "Dashed", "color": [255, 0, 0, 255], "thickness": 2 } } }
+                ] }
+            }"#,
+        );
+        let underlined = |rows: std::ops::Range<u32>| {
+            rows.flat_map(|y| (0..100).map(move |x| (x, y)))
+                .any(|(x, y)| cvs.pixmap.pixel(x, y).unwrap().red() == 255)
+        };
+        assert!(underlined(0..30));
+        assert!(underlined(30..60));
+    }
+
+    #[test]
+    fn spacing_and_justify() {
+        init_font();
+        let width = |text: &str| text_width(100., text);
+        let plain = width(r#""content": "a b""#);
+        assert!((width(r#""content": "a b", "letter_spacing": 2"#) - plain - 6.).abs() < 1e-3);
+        assert!((width(r#""content": "a b", "word_spacing": 4"#) - plain - 4.).abs() < 1e-3);
+        // the first row is stretched to the max width, the last one is not
+        assert!(width(r#""content": "aa bb cc dd ee ff", "align": "Left""#) < 100.);
+        assert_eq!(
+            width(r#""content": "aa bb cc dd ee ff", "align": "Justify""#),
+            100.
+        );
+        assert!(width(r#""content": "aa bb\ncc", "align": "Justify""#) < 100.);
+    }
+
+    #[test]
+    fn gradient_text_fill() {
+        init_font();
+        let cvs = build(
+            r#"{
+                "size": { "width": 200, "height": 60 },
+                "graphic": { "children": [
+                    { "type": "Text", "value": { "content": "HHHHHH", "size": 40, "position": { "x": 0, "y": 10 }, "color": { "type": "Gradient", "value": { "angle": 90, "stops": [
+                        { "position": { "type": "Percent", "value": 0 }, "color": [255, 0, 0, 255] },
+                        { "position": { "type": "Percent", "value": 1 }, "color": [0, 0, 255, 255] }
+                    ] } } } }
+                ] }
+            }"#,
+        );
+        let columns: Vec<_> = (0..200)
+            .filter_map(|x| {
+                (0..60)
+                    .map(|y| cvs.pixmap.pixel(x, y).unwrap())
+                    .find(|pixel| pixel.alpha() > 100)
+            })
+            .collect();
+        // the gradient runs across the text from its left to its right
+        let (first, last) = (columns[0], columns[columns.len() - 1]);
+        assert!(first.red() > first.blue());
+        assert!(last.blue() > last.red());
+    }
+
+    #[test]
+    fn row_color_box_maps_the_gradient_per_row() {
+        init_font();
+        let cvs = build(
+            r#"{
+                "size": { "width": 400, "height": 240 },
+                "graphic": { "children": [
+                    { "type": "Text", "value": { "content": "HHHHHH\nHH", "size": 40, "color_box": "Row", "position": { "x": 100, "y": 100 },
+                        "decoration": { "line": "Underline", "color": [0, 255, 0, 255], "thickness": 2 },
+                        "color": { "type": "Gradient", "value": { "angle": 90, "stops": [
+                            { "position": { "type": "Percent", "value": 0 }, "color": [255, 0, 0, 255] },
+                            { "position": { "type": "Percent", "value": 1 }, "color": [0, 0, 255, 255] }
+                        ] } } } }
+                ] }
+            }"#,
+        );
+        let pixel = |x: u32, y: u32| cvs.pixmap.pixel(x, y).unwrap();
+        // the underlines stay under their rows
+        let lines: Vec<_> = (0..240)
+            .filter(|&y| (0..400).any(|x| pixel(x, y).green() > 200))
+            .collect();
+        assert!(lines.iter().all(|y| (100..180).contains(y)));
+        assert!(lines.iter().any(|y| (140..180).contains(y)));
+        for (rows, right) in [(100..140, 250..300), (140..180, 130..180)] {
+            let columns: Vec<_> = (0..400)
+                .filter_map(|x| {
+                    rows.clone()
+                        .map(|y| pixel(x, y))
+                        .find(|pixel| pixel.alpha() > 100 && pixel.green() < 50)
+                        .map(|pixel| (x, pixel))
+                })
+                .collect();
+            let ((_, first), &(x, last)) = (columns[0], columns.last().unwrap());
+            let (_, middle) = columns[columns.len() / 2];
+            // every row keeps its glyphs and runs through the whole gradient
+            assert!(right.contains(&x));
+            assert!(first.red() > 2 * first.blue());
+            assert!(middle.red() > 60 && middle.blue() > 60);
+            assert!(last.blue() > 2 * last.red());
+        }
+    }
+
+    #[test]
+    fn color_reports_the_error_of_the_tagged_form() {
+        let color = |value: &str| serde_json::from_str::<color::Color>(value);
+        let rgba = color::Color::Rgba(color::Rgba(1, 2, 3, 4));
+        assert_eq!(color("[1, 2, 3, 4]").unwrap(), rgba);
+        assert_eq!(
+            color(r#"{ "type": "Rgba", "value": [1, 2, 3, 4] }"#).unwrap(),
+            rgba
+        );
+        assert!(color("[1, 2, 3]").is_err());
+        let error = |value: &str| color(value).unwrap_err().to_string();
+        assert!(error(r#"{ "type": "Hsl", "value": [1, 2, 3] }"#).contains("unknown variant `Hsl`"));
+        let gradient = r#"{ "type": "Gradient", "value": { "angle": 90 } }"#;
+        assert!(error(gradient).contains("missing field `stops`"));
+    }
+
+    #[test]
+    fn text_blends_over_background() {
+        init_font();
+        let cvs = build(
+            r#"{
+                "size": { "width": 100, "height": 60 },
+                "graphic": { "children": [
+                    { "type": "Container", "value": { "position_mode": "Absolute", "color": [255, 0, 0, 255], "size": { "width": 100, "height": 60 } } },
+                    { "type": "Text", "value": { "content": "HO", "color": [0, 0, 0, 255], "size": 40, "position": { "x": 10, "y": 10 } } }
+                ] }
+            }"#,
+        );
+        // anti-aliased edges do not punch holes into the container
+        assert!(cvs.pixmap.pixels().iter().all(|pixel| pixel.alpha() == 255));
+        assert!(cvs
+            .pixmap
+            .pixels()
+            .iter()
+            .any(|pixel| pixel.red() > 0 && pixel.red() < 255));
+        // every pixel of a glyph lands on its own row
+        let rows = (0..60)
+            .filter(|&y| (0..100).any(|x| cvs.pixmap.pixel(x, y).unwrap().red() == 0))
+            .count();
+        assert!(rows > 20);
+    }
+
+    #[test]
+    fn image_off_canvas_is_skipped() {
+        let source = png_data_url(4, 4, |_, _| [255, 0, 0, 255]);
+        let cvs = build(&format!(
+            r#"{{
+                "size": {{ "width": 100, "height": 100 }},
+                "graphic": {{ "children": [
+                    {{ "type": "Image", "value": {{ "source": {{ "type": "DataUrl", "value": "{source}" }}, "position": {{ "x": -200, "y": 0 }}, "size": {{ "width": 100, "height": 100 }} }} }},
+                    {{ "type": "Image", "value": {{ "source": {{ "type": "DataUrl", "value": "{source}" }}, "position": {{ "x": 0, "y": -200 }}, "size": {{ "width": 100, "height": 100 }} }} }}
+                ] }}
+            }}"#
+        ));
+        assert!(cvs.pixmap.pixels().iter().all(|pixel| pixel.alpha() == 0));
+    }
+
+    #[test]
+    fn image_fit_and_corner() {
+        // the left half of the bitmap is red and the right half is blue
+        let source = png_data_url(2, 1, |x, _| match x {
+            0 => [255, 0, 0, 255],
+            _ => [0, 0, 255, 255],
+        });
+        let image = |fit: &str, corner: &str| {
+            build(&format!(
+                r#"{{
+                    "size": {{ "width": 150, "height": 100 }},
+                    "graphic": {{ "children": [
+                        {{ "type": "Image", "value": {{ "source": {{ "type": "DataUrl", "value": "{source}" }}, "size": {{ "width": 100, "height": 100 }}, "fit": "{fit}", "corner": {corner} }} }}
+                    ] }}
+                }}"#
+            ))
+        };
+        // the 2:1 bitmap is letterboxed to 100x50 in the middle of the box
+        let cvs = image("Contain", "[0, 0, 0, 0]");
+        let pixel = |x: u32, y: u32| cvs.pixmap.pixel(x, y).unwrap();
+        assert_eq!(pixel(50, 10).alpha(), 0);
+        assert_eq!(pixel(50, 90).alpha(), 0);
+        assert_eq!(pixel(50, 50).alpha(), 255);
+        // the bitmap is scaled to 200x100 and clipped to the box
+        let cvs = image("Cover", "[0, 0, 0, 0]");
+        let pixel = |x: u32, y: u32| cvs.pixmap.pixel(x, y).unwrap();
+        assert_eq!(pixel(50, 10).alpha(), 255);
+        assert!(pixel(5, 50).red() > pixel(5, 50).blue());
+        assert!(pixel(95, 50).blue() > pixel(95, 50).red());
+        assert_eq!(pixel(120, 50).alpha(), 0);
+        // rounded corners leave the corners of the box transparent
+        let cvs = image("Fill", "[20, 20, 20, 20]");
+        let pixel = |x: u32, y: u32| cvs.pixmap.pixel(x, y).unwrap();
+        assert_eq!(pixel(1, 1).alpha(), 0);
+        assert_eq!(pixel(98, 98).alpha(), 0);
+        assert_eq!(pixel(50, 50).alpha(), 255);
+    }
+
+    #[test]
+    fn bad_image_source_is_an_error() {
+        let s = r#"{
+            "size": { "width": 100, "height": 100 },
+            "graphic": { "children": [
+                { "type": "Image", "value": { "source": { "type": "DataUrl", "value": "data:image/png;base64,not base64!" } } }
+            ] }
+        }"#;
+        let result = CanvasConfiguration::try_from(s.to_string())
+            .unwrap()
+            .build();
+        assert!(result.is_err());
+    }
+
+    fn init_font() {
+        let font = include_bytes!("../assets/default.ttf").to_vec();
+        font::init_fonts([(String::new(), font)].into()).unwrap();
+    }
+
+    /// resolved bounds of the graphics with an `id`, by the layout api
+    fn layout_of(s: &str) -> serde_json::Value {
+        serde_json::from_str(&layout(s.to_string()).unwrap()).unwrap()
+    }
+
+    /// width of a text of the fields, laid out in a box `max_width` wide
+    fn text_width(max_width: f32, fields: &str) -> f64 {
+        let bounds = layout_of(&format!(
+            r#"{{
+                "size": {{ "width": {max_width}, "height": 100 }},
+                "graphic": {{ "size": {{ "width": {max_width} }}, "children": [
+                    {{ "type": "Text", "value": {{ "id": "text", "color": [0, 0, 0, 255], "size": 20, {fields} }} }}
+                ] }}
+            }}"#
+        ));
+        bounds["text"]["width"].as_f64().unwrap()
+    }
+
+    fn build(s: &str) -> canvas::Canvas {
+        CanvasConfiguration::try_from(s.to_string())
+            .unwrap()
+            .build()
+            .unwrap()
+    }
+
+    /// `data:image/png;base64,...` of a `w` x `h` image
+    fn png_data_url(w: u32, h: u32, pixel: impl Fn(u32, u32) -> [u8; 4]) -> String {
+        let image = image::RgbaImage::from_fn(w, h, |x, y| Rgba(pixel(x, y)));
+        let mut buff = std::io::Cursor::new(vec![]);
+        DynamicImage::ImageRgba8(image)
+            .write_to(&mut buff, image::ImageOutputFormat::Png)
+            .unwrap();
+        format!(
+            "data:image/png;base64,{}",
+            base64::Engine::encode(
+                &base64::engine::general_purpose::STANDARD,
+                buff.into_inner()
+            )
+        )
+    }
+
+    fn save(buff: &[u8]) {
         let mut f = File::options()
             .write(true)
             .create(true)
+            .truncate(true)
             .open("test.png")
             .unwrap();
-        f.write(buff).unwrap();
+        f.write_all(buff).unwrap();
     }
 }
@@ -22,7 +22,7 @@ macro_rules! lt_rect {
 #[macro_export]
 macro_rules! empty_rect {
     () => {
-        crate::xywh_rect!(0., 0., 0., 0.)
+        $crate::xywh_rect!(0., 0., 0., 0.)
     };
 }
 #[macro_export]
@@ -37,7 +37,7 @@ macro_rules! paint {
 #[macro_export]
 macro_rules! rgba_paint {
     ($color: expr) => {{
-        let mut paint = crate::paint!();
+        let mut paint = $crate::paint!();
         paint.shader = Shader::SolidColor($color.into());
         paint
     }};
@@ -75,7 +75,7 @@ macro_rules! merge_pixmap {
         let mut mask;
         let mut option_mask = None;
         if (bw > aw || bh > ah) {
-            let mut new_pixmap = crate::empty_pixmap!(aw.max(bw), ah.max(bh));
+            let mut new_pixmap = $crate::empty_pixmap!(aw.max(bw), ah.max(bh));
             if let Some(path) = $path {
                 mask = tiny_skia::ClipMask::new();
                 mask.set_path(
@@ -130,7 +130,7 @@ macro_rules! expand_pixmap {
         let pw = $pixmap.width() as f32;
         let ph = $pixmap.height() as f32;
         if (bw > pw || bh > ph) {
-            let mut new_pixmap = crate::empty_pixmap!(bw.max(pw) as u32, bh.max(ph) as u32);
+            let mut new_pixmap = $crate::empty_pixmap!(bw.max(pw) as u32, bh.max(ph) as u32);
             new_pixmap.draw_pixmap(
                 0,
                 0,
@@ -149,7 +149,7 @@ macro_rules! expand_pixmap {
 #[macro_export]
 macro_rules! clip_pixmap {
     ($pixmap: expr, $bounds: expr) => {{
-        let mut new_pixmap = crate::empty_pixmap!($bounds.width(), $bounds.height());
+        let mut new_pixmap = $crate::empty_pixmap!($bounds.width(), $bounds.height());
         new_pixmap.draw_pixmap(
             0,
             0,
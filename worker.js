@@ -1,11 +1,13 @@
 const wasm = require('./pkg/canvaskit')
 const options = JSON.stringify({
-  size: [1200, 800],
+  size: { width: 1200, height: 800 },
   background: { type: 'Rgba', value: [255, 255, 0, 200] },
-  "graphics":[
-    { "type": "Rectangle", "value": { "corner": [24, 24, 24, 24], "color": { "type": "Rgba", "value": [255, 0, 0, 120] }, "position": [30, 30], "size": [400, 300] } },
-    { "type": "Rectangle", "value": { "corner": [24, 24, 24, 24], "color": { "type": "Rgba", "value": [255, 0, 255, 100] }, "position": [100, 50], "size": [700, 600] } },
-  ]
+  graphic: {
+    children: [
+      { "type": "Container", "value": { "corner": [24, 24, 24, 24], "color": { "type": "Rgba", "value": [255, 0, 0, 120] }, "position": { x: 30, y: 30 }, "size": { width: 400, height: 300 } } },
+      { "type": "Container", "value": { "corner": [24, 24, 24, 24], "color": { "type": "Rgba", "value": [255, 0, 255, 100] }, "position": { x: 100, y: 50 }, "size": { width: 700, height: 600 } } },
+    ]
+  }
 })
 
 const buff = wasm.draw(options)